@@ -1,339 +1,3100 @@
-#!/usr/bin/env rust-script
-//! Bump Flutter Version
-//!
-//! Updates the version in pubspec.yaml.
-//! Also ensures a lightweight git tag exists for the *current* version before bumping.
-//!
-//! ## What it does
-//! - Reads the current `version:` from `pubspec.yaml` (YAML parser, with a regex fallback).
-//! - Checks if a tag already exists for that version (`vX.Y.Z` or `X.Y.Z`, including prerelease).
-//! - If neither exists, creates a **lightweight** tag pointing at `HEAD`.
-//! - Then bumps the version in `pubspec.yaml` by the requested part.
-//!
-//! ## Revert
-//! If you bumped by mistake, use `revert` to restore pubspec.yaml from the last git commit.
-//!
-//! Notes:
-//! - Tag creation is **local only** (no fetch/push).
-//! - If not in a git repo, if `HEAD` is unborn (no commits), or if the version can't be read,
-//!   the tag step is skipped.
-//! - Writing the new `version:` uses a regex replace to preserve formatting/comments.
-//!
-//! Usage:
-//!   rust-script bump_version.rs <major|minor|patch|build> [--pubspec PATH] [--tag-prefix v|none]
-//!   rust-script bump_version.rs revert [--pubspec PATH]
-//!
-//! Examples:
-//! - Patch bump, default tag prefix `v`:
-//!   `rust-script bump_version.rs patch`
-//! - Build bump using a different pubspec:
-//!   `rust-script bump_version.rs build --pubspec path/to/pubspec.yaml`
-//! - Create tags without `v` prefix:
-//!   `rust-script bump_version.rs minor --tag-prefix none`
-//! - Revert the last bump:
-//!   `rust-script bump_version.rs revert`
-//!
-//! ```cargo
-//! [dependencies]
-//! clap = { version = "4.4", features = ["derive"] }
-//! regex = "1.10"
-//! anyhow = "1.0"
-//! semver = "1.0"
-//! gix = "0.78"
-//! serde = { version = "1.0", features = ["derive"] }
-//! serde_yaml = "0.9"
-//! ```
-
-use clap::{Parser, Subcommand, ValueEnum};
-use std::fs;
-use std::path::Path;
-use anyhow::{Context, Result};
-use regex::Regex;
-use semver::{Version, Prerelease, BuildMetadata};
-use gix::refs::transaction::PreviousValue;
-use serde::Deserialize;
-
-#[derive(Parser)]
-#[command(author, version, about, long_about = None)]
-struct Args {
-    #[command(subcommand)]
-    command: Command,
-}
-
-#[derive(Subcommand)]
-enum Command {
-    /// Bump major version (X.0.0+1)
-    Major {
-        #[arg(long, default_value = "pubspec.yaml")]
-        pubspec: String,
-        #[arg(long, value_enum, default_value = "v")]
-        tag_prefix: TagPrefix,
-    },
-    /// Bump minor version (x.Y.0+1)
-    Minor {
-        #[arg(long, default_value = "pubspec.yaml")]
-        pubspec: String,
-        #[arg(long, value_enum, default_value = "v")]
-        tag_prefix: TagPrefix,
-    },
-    /// Bump patch version (x.y.Z+1)
-    Patch {
-        #[arg(long, default_value = "pubspec.yaml")]
-        pubspec: String,
-        #[arg(long, value_enum, default_value = "v")]
-        tag_prefix: TagPrefix,
-    },
-    /// Bump build number only (x.y.z+N)
-    Build {
-        #[arg(long, default_value = "pubspec.yaml")]
-        pubspec: String,
-        #[arg(long, value_enum, default_value = "v")]
-        tag_prefix: TagPrefix,
-    },
-    /// Revert the last bump by restoring pubspec.yaml from git HEAD
-    Revert {
-        #[arg(long, default_value = "pubspec.yaml")]
-        pubspec: String,
-    },
-}
-
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
-enum TagPrefix {
-    V,
-    None,
-}
-
-#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
-enum VersionPart {
-    Major,
-    Minor,
-    Patch,
-    Build,
-}
-
-fn tag_exists(repo: &gix::Repository, tag: &str) -> Result<bool> {
-    let full = format!("refs/tags/{tag}");
-    Ok(repo.try_find_reference(full.as_str())?.is_some())
-}
-
-#[derive(Debug, Deserialize)]
-struct PubspecYaml {
-    version: Option<String>,
-}
-
-fn read_pubspec_version(content: &str) -> Option<String> {
-    // YAML parse (preferred): robust against indentation/ordering differences.
-    if let Ok(doc) = serde_yaml::from_str::<PubspecYaml>(content) {
-        if let Some(v) = doc.version {
-            let v = v.trim().to_string();
-            if !v.is_empty() {
-                return Some(v);
-            }
-        }
-    }
-
-    // Fallback: handle partial/invalid YAML while still supporting the common case.
-    let version_line_regex = Regex::new(r"(?m)^version:\s*(.+)$").ok()?;
-    version_line_regex
-        .captures(content)
-        .map(|c| c[1].trim().to_string())
-        .filter(|s| !s.is_empty())
-}
-
-fn ensure_current_version_tag(pubspec_path: &Path, tag_prefix: TagPrefix) -> Result<()> {
-    let start_dir = pubspec_path
-        .parent()
-        .filter(|p| !p.as_os_str().is_empty())
-        .unwrap_or_else(|| Path::new("."));
-    let repo = match gix::discover(start_dir) {
-        Ok(r) => r,
-        Err(_) => {
-            println!("[bump-version] Skipping tag check (not in a git repository)");
-            return Ok(());
-        }
-    };
-
-    let content = fs::read_to_string(pubspec_path)
-        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
-
-    let version_str = match read_pubspec_version(&content) {
-        Some(v) => v,
-        None => {
-            println!("[bump-version] Skipping tag check (no version found in pubspec)");
-            return Ok(());
-        }
-    };
-    let v = match Version::parse(&version_str) {
-        Ok(v) => v,
-        Err(e) => {
-            println!(
-                "[bump-version] Skipping tag check (invalid semver in pubspec '{}'): {}",
-                version_str, e
-            );
-            return Ok(());
-        }
-    };
-
-    // Tag name: use semver core (+ optional prerelease), ignore build metadata.
-    let mut base = format!("{}.{}.{}", v.major, v.minor, v.patch);
-    if !v.pre.is_empty() {
-        base = format!("{}-{}", base, v.pre);
-    }
-    let tag_plain = base.clone();
-    let tag_v = format!("v{}", base);
-
-    let preferred_tag = match tag_prefix {
-        TagPrefix::V => tag_v.clone(),
-        TagPrefix::None => tag_plain.clone(),
-    };
-
-    // If either convention exists, do nothing.
-    if tag_exists(&repo, &tag_plain)? || tag_exists(&repo, &tag_v)? {
-        println!(
-            "[bump-version] Tag already exists for current version: {} (checked '{}' and '{}')",
-            version_str, tag_plain, tag_v
-        );
-        return Ok(());
-    }
-
-    let head_id = match repo.head_id() {
-        Ok(id) => id.detach(),
-        Err(_) => {
-            println!("[bump-version] Skipping tag creation (repository has no commits yet)");
-            return Ok(());
-        }
-    };
-
-    repo.tag_reference(&preferred_tag, head_id, PreviousValue::MustNotExist)
-        .with_context(|| format!("Failed to create lightweight tag '{preferred_tag}'"))?;
-    println!(
-        "[bump-version] Created lightweight tag '{}' for current version {}",
-        preferred_tag, version_str
-    );
-    Ok(())
-}
-
-fn revert_bump(pubspec_path: &Path) -> Result<()> {
-    let content = fs::read_to_string(pubspec_path)
-        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
-    let current_version = read_pubspec_version(&content)
-        .unwrap_or_else(|| "<unknown>".to_string());
-
-    // Use git to check if pubspec has uncommitted changes vs HEAD
-    let diff_output = std::process::Command::new("git")
-        .args(["diff", "HEAD", "--name-only", "--", pubspec_path.to_str().unwrap()])
-        .output()
-        .context("Failed to run 'git diff'")?;
-
-    let diff_files = String::from_utf8_lossy(&diff_output.stdout);
-    if diff_files.trim().is_empty() {
-        println!("[bump-version] Nothing to revert ({} has no changes vs HEAD)", pubspec_path.display());
-        return Ok(());
-    }
-
-    // Restore pubspec.yaml from HEAD
-    let status = std::process::Command::new("git")
-        .args(["checkout", "HEAD", "--", pubspec_path.to_str().unwrap()])
-        .status()
-        .context("Failed to run 'git checkout'")?;
-
-    if !status.success() {
-        anyhow::bail!("git checkout HEAD -- {} failed", pubspec_path.display());
-    }
-
-    let restored_content = fs::read_to_string(pubspec_path)
-        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
-    let restored_version = read_pubspec_version(&restored_content)
-        .unwrap_or_else(|| "<unknown>".to_string());
-
-    println!("[bump-version] Reverted: {} → {}", current_version, restored_version);
-    Ok(())
-}
-
-fn do_bump(pubspec_path: &Path, part: VersionPart, tag_prefix: TagPrefix) -> Result<()> {
-    // Ensure the current version is tagged before bumping.
-    ensure_current_version_tag(pubspec_path, tag_prefix)?;
-
-    let content = fs::read_to_string(pubspec_path)
-        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
-
-    // Regex to locate the version line, preserving indentation and formatting
-    let version_line_regex = Regex::new(r"(?m)^version:\s*(.+)$").unwrap();
-
-    let mut new_version_string = String::new();
-
-    let new_content = version_line_regex.replace(&content, |caps: &regex::Captures| {
-        let old_version_str = caps[1].trim();
-        // Use semver crate to parse the version string
-        let mut v = Version::parse(old_version_str)
-            .unwrap_or_else(|e| panic!("Invalid semver format in pubspec.yaml '{}': {}", old_version_str, e));
-
-        // Helper to parse numeric build number (Flutter standard)
-        // Returns 0 if no build number or not numeric
-        let current_build_num: u64 = if v.build.is_empty() {
-            0
-        } else {
-            v.build.as_str().parse().unwrap_or(0)
-        };
-
-        match part {
-            VersionPart::Major => {
-                v.major += 1;
-                v.minor = 0;
-                v.patch = 0;
-                v.pre = Prerelease::EMPTY;
-                v.build = BuildMetadata::new("1").unwrap();
-            }
-            VersionPart::Minor => {
-                v.minor += 1;
-                v.patch = 0;
-                v.pre = Prerelease::EMPTY;
-                v.build = BuildMetadata::new("1").unwrap();
-            }
-            VersionPart::Patch => {
-                v.patch += 1;
-                v.pre = Prerelease::EMPTY;
-                v.build = BuildMetadata::new("1").unwrap();
-            }
-            VersionPart::Build => {
-                let new_build = current_build_num + 1;
-                v.build = BuildMetadata::new(&new_build.to_string()).unwrap();
-            }
-        }
-
-        new_version_string = v.to_string();
-        format!("version: {}", new_version_string)
-    });
-
-    if new_version_string.is_empty() {
-        println!("No version line found in {}", pubspec_path.display());
-        return Ok(());
-    }
-
-    fs::write(pubspec_path, new_content.to_string())?;
-    println!("Bumped version to: {}", new_version_string);
-
-    Ok(())
-}
-
-fn main() -> Result<()> {
-    let args = Args::parse();
-
-    match args.command {
-        Command::Major { pubspec, tag_prefix } => {
-            do_bump(Path::new(&pubspec), VersionPart::Major, tag_prefix)
-        }
-        Command::Minor { pubspec, tag_prefix } => {
-            do_bump(Path::new(&pubspec), VersionPart::Minor, tag_prefix)
-        }
-        Command::Patch { pubspec, tag_prefix } => {
-            do_bump(Path::new(&pubspec), VersionPart::Patch, tag_prefix)
-        }
-        Command::Build { pubspec, tag_prefix } => {
-            do_bump(Path::new(&pubspec), VersionPart::Build, tag_prefix)
-        }
-        Command::Revert { pubspec } => {
-            revert_bump(Path::new(&pubspec))
-        }
-    }
-}
+#!/usr/bin/env rust-script
+//! Bump Flutter Version
+//!
+//! Updates the version in pubspec.yaml.
+//! Also ensures a lightweight git tag exists for the *current* version before bumping.
+//!
+//! ## What it does
+//! - Reads the current `version:` from `pubspec.yaml` (YAML parser, with a line-based fallback).
+//! - Checks if a tag already exists for that version (`vX.Y.Z` or `X.Y.Z`, including prerelease).
+//! - If neither exists, creates a **lightweight** tag pointing at `HEAD`.
+//! - Then bumps the version in `pubspec.yaml` by the requested part.
+//!
+//! ## Prereleases
+//! `pre <alpha|beta|rc>` starts a prerelease (bumping the patch, or `--bump` part, first)
+//! or advances the current one (`1.3.0-beta.1` → `1.3.0-beta.2`, `1.3.0-beta.2` → `1.3.0-rc.1`).
+//! `promote` drops the prerelease (`1.3.0-rc.2` → `1.3.0`).
+//!
+//! ## Automatic bump
+//! `auto` walks the commits since the last version tag and picks the part from
+//! Conventional Commits: `feat` → minor, `fix`/`perf` → patch, `!` or `BREAKING CHANGE` → major.
+//! While the major version is 0, breaking changes bump the minor and features the patch.
+//! Nothing is bumped when no commit qualifies.
+//!
+//! ## Build numbers
+//! `--build-strategy` picks the new `+N`: `reset` (default: 1 after major/minor/patch,
+//! +1 otherwise), `monotonic` (always +1), `commit-count` (commits on HEAD), `date`
+//! (YYMMDDNN) or `env:VAR` (e.g. a CI run number). Except for `reset`, a result that
+//! doesn't exceed the previous build number is an error.
+//!
+//! ## Set and show
+//! `set <VERSION>` writes an exact version; without `+N` the build number follows
+//! `--build-strategy`. `show` prints the current version and its parts as text, JSON
+//! or shell `KEY=VALUE` lines (`eval "$(rust-script bump_version.rs show --format shell)"`).
+//!
+//! ## Extra version files
+//! Other files carrying the version are listed in a `bump_version:` section of pubspec.yaml
+//! (paths relative to it). Each entry names one locator; only the located value is rewritten.
+//! `value: name` writes the version without `+N` (default `full`).
+//!
+//! ```yaml
+//! bump_version:
+//!   files:
+//!     - file: app.toml
+//!       toml: pubspec.version
+//!     - file: app.pkl
+//!       pkl: pubspec.version
+//!     - file: web/package.json
+//!       json: /version
+//!       value: name
+//!     - file: CHANGELOG.md
+//!       regex: '^## \[Unreleased\] - (\S+)'
+//!       value: name
+//!     - file: config/meta.yaml
+//!       yaml: app.version
+//! ```
+//!
+//! ## Release
+//! `release <part>` runs the whole release: it requires a clean working tree on an allowed
+//! branch (`--allow-branch`, default `main`/`master`), bumps the version, commits the changed
+//! files as `bump version to X`, tags the new version and, with `--push`, pushes the branch
+//! and tags atomically. If a step fails, the tags, commit and file changes made so far are
+//! rolled back. `--dry-run` prints the plan without changing anything.
+//!
+//! ## Workspaces
+//! `--package NAME` (repeatable) or `--all-packages` bumps packages of the workspace rooted at
+//! `--pubspec`: the root itself (by its `name:`) and the packages found through the root's
+//! `workspace:` list, or `bump_version.packages` globs when set (`packages: ["packages/*"]`).
+//! Dependents' `^x.y.z` or exact constraints on a bumped package (also the `version:` next
+//! to a `path:` dependency) are updated; other ranges are reported and left alone. Packages are
+//! tagged `{name}-v{version}` (set `bump_version.package_tag` to change it); the root keeps
+//! `--tag-prefix`. Each package's bump is journaled separately; revert one with
+//! `revert --pubspec packages/core/pubspec.yaml`. `auto` and `release` still work on a single
+//! pubspec.
+//!
+//! ## Dry run and JSON
+//! Every bump command takes `--dry-run`, which prints the old and new versions, the tags it
+//! would create and the files it would touch without writing anything, and `--json`, which
+//! prints the same on stdout (progress goes to stderr):
+//!
+//! ```json
+//! {
+//!   "dry_run": true,
+//!   "bumps": [
+//!     {
+//!       "package": null,
+//!       "pubspec": "pubspec.yaml",
+//!       "old_version": "1.2.3+5",
+//!       "new_version": "1.2.4+1",
+//!       "tags": ["v1.2.3"],
+//!       "files": ["pubspec.yaml"]
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! `bumps` is empty when nothing would be bumped (e.g. `auto` without qualifying commits).
+//!
+//! ## Revert
+//! Every bump is recorded in a small journal (`.git/bump_version_journal.json`): old and new
+//! version, the tags it created and the `release` commit, if any. `revert` undoes the last
+//! recorded bump of the pubspec: it restores the files, or, once the bump is committed, drops
+//! the commit (`--commit reset`, only while it is HEAD and unpushed) or adds a revert commit
+//! (`--commit revert`); without `--commit` it asks. The tags the bump created are deleted.
+//! Without a matching journal entry, it restores pubspec.yaml (and the extra version files)
+//! from the last git commit.
+//!
+//! Notes:
+//! - Tag creation is **local only** (no fetch/push), except for `release --push`.
+//! - If not in a git repo, if `HEAD` is unborn (no commits), or if the version can't be read,
+//!   the tag step is skipped.
+//! - Only the `version:` value is rewritten, keeping quotes, comments and line endings.
+//!   The line edited must be the top-level key the YAML parser reads.
+//!
+//! Usage:
+//!   rust-script bump_version.rs <major|minor|patch|build> [--pubspec PATH] [--tag-prefix v|none] [--build-strategy S] [--package NAME]... [--all-packages] [--dry-run] [--json]
+//!   rust-script bump_version.rs auto [--pubspec PATH] [--tag-prefix v|none]
+//!   rust-script bump_version.rs pre <alpha|beta|rc> [--bump major|minor|patch] [--pubspec PATH] [--tag-prefix v|none]
+//!   rust-script bump_version.rs promote [--pubspec PATH] [--tag-prefix v|none]
+//!   rust-script bump_version.rs set <VERSION> [--pubspec PATH] [--tag-prefix v|none] [--build-strategy S]
+//!   rust-script bump_version.rs release <major|minor|patch|build> [--allow-branch B]... [--push] [--remote R] [--pubspec PATH] [--tag-prefix v|none] [--build-strategy S] [--dry-run] [--json]
+//!   rust-script bump_version.rs show [--pubspec PATH] [--format text|json|shell]
+//!   rust-script bump_version.rs revert [--pubspec PATH] [--commit reset|revert]
+//!
+//! Every bump command (all but `show` and `revert`) takes the options listed for `major`.
+//!
+//! Examples:
+//! - Patch bump, default tag prefix `v`:
+//!   `rust-script bump_version.rs patch`
+//! - Build bump using a different pubspec:
+//!   `rust-script bump_version.rs build --pubspec path/to/pubspec.yaml`
+//! - Create tags without `v` prefix:
+//!   `rust-script bump_version.rs minor --tag-prefix none`
+//! - Bump according to the commits since the last release:
+//!   `rust-script bump_version.rs auto`
+//! - Start a beta for the next minor release (1.2.3 → 1.3.0-beta.1):
+//!   `rust-script bump_version.rs pre beta --bump minor`
+//! - Release the prerelease (1.3.0-rc.2 → 1.3.0):
+//!   `rust-script bump_version.rs promote`
+//! - Patch release that keeps counting the build number (1.2.3+57 → 1.2.4+58):
+//!   `rust-script bump_version.rs patch --build-strategy monotonic`
+//! - Set the version for a planned release:
+//!   `rust-script bump_version.rs set 2.0.0`
+//! - Bump the `core` package and the constraints on it in the workspace:
+//!   `rust-script bump_version.rs minor --package core`
+//! - Release a patch and push it to trigger CI:
+//!   `rust-script bump_version.rs release patch --push`
+//! - Compute the next version in CI without changing anything:
+//!   `rust-script bump_version.rs auto --dry-run --json`
+//! - Read the version in CI:
+//!   `rust-script bump_version.rs show --format json`
+//! - Revert the last bump:
+//!   `rust-script bump_version.rs revert`
+//!
+//! ```cargo
+//! [dependencies]
+//! clap = { version = "4.4", features = ["derive"] }
+//! regex = "1.10"
+//! anyhow = "1.0"
+//! semver = "1.0"
+//! gix = { version = "0.78", features = ["tree-editor"] }
+//! serde = { version = "1.0", features = ["derive"] }
+//! serde_yaml = "0.9"
+//! serde_json = "1.0"
+//! glob = "0.3"
+//! ```
+
+use clap::{Parser, Subcommand, ValueEnum};
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use regex::Regex;
+use semver::{Version, Prerelease, BuildMetadata};
+use gix::index::entry::Stage;
+use gix::refs::transaction::PreviousValue;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Set by `--json`, which keeps stdout for the JSON report.
+static LOG_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Progress output: stdout, or stderr with `--json`.
+macro_rules! log {
+    ($($arg:tt)*) => {
+        if LOG_TO_STDERR.load(Ordering::Relaxed) {
+            eprintln!($($arg)*);
+        } else {
+            println!($($arg)*);
+        }
+    };
+}
+
+#[derive(Parser)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Bump major version (X.0.0+1)
+    Major {
+        #[command(flatten)]
+        opts: BumpOptions,
+    },
+    /// Bump minor version (x.Y.0+1)
+    Minor {
+        #[command(flatten)]
+        opts: BumpOptions,
+    },
+    /// Bump patch version (x.y.Z+1)
+    Patch {
+        #[command(flatten)]
+        opts: BumpOptions,
+    },
+    /// Bump build number only (x.y.z+N)
+    Build {
+        #[command(flatten)]
+        opts: BumpOptions,
+    },
+    /// Bump the part implied by Conventional Commits since the last version tag
+    Auto {
+        #[command(flatten)]
+        opts: BumpOptions,
+    },
+    /// Start or advance a prerelease (x.y.z-beta.N+1)
+    Pre {
+        #[arg(value_enum)]
+        channel: PreChannel,
+        /// Core part to bump first (default: patch when starting from a release)
+        #[arg(long, value_enum)]
+        bump: Option<VersionPart>,
+        #[command(flatten)]
+        opts: BumpOptions,
+    },
+    /// Drop the prerelease to produce the final version (x.y.z+1)
+    Promote {
+        #[command(flatten)]
+        opts: BumpOptions,
+    },
+    /// Set an exact version (x.y.z[-pre][+N])
+    Set {
+        #[arg(value_parser = parse_version)]
+        version: Version,
+        #[command(flatten)]
+        opts: BumpOptions,
+    },
+    /// Bump, commit and tag a release, optionally pushing it
+    Release {
+        #[arg(value_enum)]
+        part: VersionPart,
+        #[command(flatten)]
+        opts: BumpOptions,
+        #[command(flatten)]
+        release: ReleaseOptions,
+    },
+    /// Print the current version and its parts
+    Show {
+        #[arg(long, default_value = "pubspec.yaml")]
+        pubspec: String,
+        #[arg(long, value_enum, default_value = "text")]
+        format: ShowFormat,
+    },
+    /// Revert the last bump: restore its files, delete its tags and undo its commit
+    Revert {
+        #[arg(long, default_value = "pubspec.yaml")]
+        pubspec: String,
+        /// How to undo a bump that was already committed (asked interactively otherwise)
+        #[arg(long, value_enum)]
+        commit: Option<CommitAction>,
+    },
+}
+
+/// Options shared by every bump command.
+#[derive(clap::Args, Clone, Debug)]
+struct BumpOptions {
+    #[arg(long, default_value = "pubspec.yaml")]
+    pubspec: String,
+    #[arg(long, value_enum, default_value = "v")]
+    tag_prefix: TagPrefix,
+    /// Build number source: reset, monotonic, commit-count, date (YYMMDDNN) or env:VAR
+    #[arg(long, default_value = "reset", value_parser = parse_build_strategy)]
+    build_strategy: BuildStrategy,
+    /// Workspace package to bump, by pubspec name (repeatable)
+    #[arg(long = "package", conflicts_with = "all_packages")]
+    packages: Vec<String>,
+    /// Bump every workspace package that has a version
+    #[arg(long)]
+    all_packages: bool,
+    /// Print what would change without writing files or creating tags
+    #[arg(long)]
+    dry_run: bool,
+    /// Print the bump as JSON on stdout (progress goes to stderr)
+    #[arg(long)]
+    json: bool,
+}
+
+impl BumpOptions {
+    /// Whether workspace packages are bumped instead of the single `--pubspec`.
+    fn selects_packages(&self) -> bool {
+        self.all_packages || !self.packages.is_empty()
+    }
+}
+
+/// Options of the `release` command.
+#[derive(clap::Args, Clone, Debug)]
+struct ReleaseOptions {
+    /// Branch releases may be made from (repeatable)
+    #[arg(long = "allow-branch", default_values = ["main", "master"])]
+    allow_branches: Vec<String>,
+    /// Push the release commit and tags
+    #[arg(long)]
+    push: bool,
+    #[arg(long, default_value = "origin")]
+    remote: String,
+}
+
+impl Command {
+    /// Options of the commands that bump.
+    fn bump_options(&self) -> Option<&BumpOptions> {
+        match self {
+            Command::Major { opts }
+            | Command::Minor { opts }
+            | Command::Patch { opts }
+            | Command::Build { opts }
+            | Command::Auto { opts }
+            | Command::Pre { opts, .. }
+            | Command::Promote { opts }
+            | Command::Set { opts, .. }
+            | Command::Release { opts, .. } => Some(opts),
+            Command::Show { .. } | Command::Revert { .. } => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+enum ShowFormat {
+    Text,
+    Json,
+    Shell,
+}
+
+/// How `revert` undoes a committed bump.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+enum CommitAction {
+    /// Drop the bump commit (only while it is HEAD and unpushed)
+    Reset,
+    /// Add a commit reverting the bump
+    Revert,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+enum TagPrefix {
+    V,
+    None,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+enum VersionPart {
+    Major,
+    Minor,
+    Patch,
+    Build,
+}
+
+/// Prerelease channels, in precedence order.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Debug)]
+enum PreChannel {
+    Alpha,
+    Beta,
+    Rc,
+}
+
+impl PreChannel {
+    fn as_str(self) -> &'static str {
+        match self {
+            PreChannel::Alpha => "alpha",
+            PreChannel::Beta => "beta",
+            PreChannel::Rc => "rc",
+        }
+    }
+}
+
+/// How the build number (`+N`) of the new version is chosen.
+#[derive(Clone, Debug)]
+enum BuildStrategy {
+    /// Restart at 1 on major/minor/patch, +1 otherwise.
+    Reset,
+    /// Previous build number + 1.
+    Monotonic,
+    /// Number of commits reachable from HEAD.
+    CommitCount,
+    /// YYMMDDNN, NN counting builds of the day.
+    Date,
+    /// Numeric value of an environment variable (e.g. a CI run number).
+    Env(String),
+    /// No build number; used for workspace packages that have none.
+    Omit,
+}
+
+impl std::fmt::Display for BuildStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildStrategy::Reset => f.write_str("reset"),
+            BuildStrategy::Monotonic => f.write_str("monotonic"),
+            BuildStrategy::CommitCount => f.write_str("commit-count"),
+            BuildStrategy::Date => f.write_str("date"),
+            BuildStrategy::Env(var) => write!(f, "env:{}", var),
+            BuildStrategy::Omit => f.write_str("omit"),
+        }
+    }
+}
+
+fn parse_version(value: &str) -> Result<Version, String> {
+    let v = Version::parse(value.trim()).map_err(|e| format!("invalid semver '{}': {}", value, e))?;
+    if !v.build.is_empty() && v.build.as_str().parse::<u64>().is_err() {
+        return Err(format!(
+            "build number '{}' must be an integer (Flutter uses it as the build number)",
+            v.build
+        ));
+    }
+    Ok(v)
+}
+
+fn parse_build_strategy(value: &str) -> Result<BuildStrategy, String> {
+    match value {
+        "reset" => Ok(BuildStrategy::Reset),
+        "monotonic" => Ok(BuildStrategy::Monotonic),
+        "commit-count" => Ok(BuildStrategy::CommitCount),
+        "date" => Ok(BuildStrategy::Date),
+        _ => match value.strip_prefix("env:") {
+            Some(var) if !var.is_empty() => Ok(BuildStrategy::Env(var.to_string())),
+            _ => Err(format!(
+                "expected reset, monotonic, commit-count, date or env:VAR, got '{}'",
+                value
+            )),
+        },
+    }
+}
+
+/// Release impact of a commit under Conventional Commits, lowest first.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+enum Impact {
+    Patch,
+    Minor,
+    Breaking,
+}
+
+#[derive(Clone, Debug)]
+enum Bump {
+    Part(VersionPart),
+    Pre {
+        channel: PreChannel,
+        part: Option<VersionPart>,
+    },
+    Promote,
+    Set(Version),
+}
+
+fn tag_exists(repo: &gix::Repository, tag: &str) -> Result<bool> {
+    let full = format!("refs/tags/{tag}");
+    Ok(repo.try_find_reference(full.as_str())?.is_some())
+}
+
+#[derive(Debug, Deserialize)]
+struct PubspecYaml {
+    version: Option<String>,
+}
+
+/// The `bump_version:` section of pubspec.yaml.
+#[derive(Debug, Default, Deserialize)]
+struct BumpConfig {
+    #[serde(default)]
+    files: Vec<VersionFile>,
+    /// Globs of workspace package directories, relative to pubspec.yaml. Defaults to the
+    /// `workspace:` list.
+    #[serde(default)]
+    packages: Vec<String>,
+    /// Tag name of workspace packages; `{name}` and `{version}` are replaced.
+    package_tag: Option<String>,
+}
+
+/// Default `package_tag`, e.g. `core-v1.2.3`.
+const DEFAULT_PACKAGE_TAG: &str = "{name}-v{version}";
+
+/// The fields of a pubspec that identify a workspace package.
+#[derive(Debug, Deserialize)]
+struct PubspecPackage {
+    name: Option<String>,
+    #[serde(default)]
+    workspace: Vec<String>,
+}
+
+/// A package of the workspace rooted at `--pubspec`.
+#[derive(Debug)]
+struct Package {
+    name: String,
+    pubspec_path: PathBuf,
+    version: Option<String>,
+    /// The root pubspec itself, tagged like a single-package repo.
+    root: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct PubspecBumpConfig {
+    #[serde(default)]
+    bump_version: Option<BumpConfig>,
+}
+
+/// Another file holding the version, relative to pubspec.yaml.
+#[derive(Debug, Deserialize)]
+struct VersionFile {
+    file: String,
+    #[serde(flatten)]
+    locator: Locator,
+    #[serde(default)]
+    value: VersionValue,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Locator {
+    /// Dotted key of a block mapping, e.g. `app.version`.
+    Yaml(String),
+    /// JSON pointer, e.g. `/version`.
+    Json(String),
+    /// Dotted key, e.g. `pubspec.version` (table header or dotted key).
+    Toml(String),
+    /// Dotted property path, e.g. `pubspec.version`.
+    Pkl(String),
+    /// Pattern whose `version` group (or first group) holds the version.
+    Regex(String),
+}
+
+#[derive(Copy, Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum VersionValue {
+    /// `1.2.3-beta.1+4`
+    #[default]
+    Full,
+    /// `1.2.3-beta.1`, without the build number.
+    Name,
+}
+
+impl std::fmt::Display for Locator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Locator::Yaml(key) => write!(f, "YAML key '{}'", key),
+            Locator::Json(pointer) => write!(f, "JSON pointer '{}'", pointer),
+            Locator::Toml(key) => write!(f, "TOML key '{}'", key),
+            Locator::Pkl(property) => write!(f, "Pkl property '{}'", property),
+            Locator::Regex(pattern) => write!(f, "regex '{}'", pattern),
+        }
+    }
+}
+
+impl VersionValue {
+    /// The text written for `version`.
+    fn render(self, version: &Version) -> String {
+        match self {
+            VersionValue::Full => version.to_string(),
+            VersionValue::Name => {
+                let mut name = version.clone();
+                name.build = BuildMetadata::EMPTY;
+                name.to_string()
+            }
+        }
+    }
+}
+
+fn read_bump_config(content: &str, pubspec_path: &Path) -> Result<BumpConfig> {
+    let doc: PubspecBumpConfig = serde_yaml::from_str(content).with_context(|| {
+        format!("Invalid bump_version section in {}", pubspec_path.display())
+    })?;
+    Ok(doc.bump_version.unwrap_or_default())
+}
+
+fn read_pubspec_version(content: &str) -> Option<String> {
+    // YAML parse (preferred): robust against indentation/ordering differences.
+    if let Ok(doc) = serde_yaml::from_str::<PubspecYaml>(content) {
+        if let Some(v) = doc.version {
+            let v = v.trim().to_string();
+            if !v.is_empty() {
+                return Some(v);
+            }
+        }
+    }
+
+    // Fallback: handle partial/invalid YAML while still supporting the common case.
+    yaml_value_span(content, "version")
+        .map(|span| content[span].trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// Locate the top-level `version:` value for editing, making sure the line found is the
+/// one the YAML parser reads. `None` if the pubspec has no version.
+fn locate_pubspec_version(
+    content: &str,
+    pubspec_path: &Path,
+) -> Result<Option<(Range<usize>, Version)>> {
+    let span = yaml_value_span(content, "version");
+    // Only a document that parses is checked; invalid YAML falls back to the line.
+    let parsed = serde_yaml::from_str::<PubspecYaml>(content)
+        .ok()
+        .map(|doc| {
+            doc.version
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        });
+    let span = match (span, parsed) {
+        (None, Some(Some(version))) => anyhow::bail!(
+            "{} has version '{}', but not as a plain top-level `version:` line that can be edited",
+            pubspec_path.display(),
+            version
+        ),
+        (None, _) => return Ok(None),
+        (Some(span), Some(parsed)) if parsed.as_deref() != Some(content[span.clone()].trim()) => {
+            anyhow::bail!(
+                "The `version:` line in {} reads '{}', but the YAML version is {}",
+                pubspec_path.display(),
+                &content[span],
+                parsed.map_or("missing".to_string(), |v| format!("'{}'", v))
+            )
+        }
+        (Some(span), _) => span,
+    };
+    let version_str = content[span.clone()].trim();
+    let version = Version::parse(version_str).with_context(|| {
+        format!(
+            "Invalid semver in {} '{}'",
+            pubspec_path.display(),
+            version_str
+        )
+    })?;
+    Ok(Some((span, version)))
+}
+
+/// Directory to start git discovery from.
+fn pubspec_dir(pubspec_path: &Path) -> &Path {
+    pubspec_path
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."))
+}
+
+/// Tag name for `v`: semver core (+ optional prerelease), ignoring build metadata.
+fn version_tag(v: &Version, tag_prefix: TagPrefix) -> String {
+    let mut base = format!("{}.{}.{}", v.major, v.minor, v.patch);
+    if !v.pre.is_empty() {
+        base = format!("{}-{}", base, v.pre);
+    }
+    match tag_prefix {
+        TagPrefix::V => format!("v{}", base),
+        TagPrefix::None => base,
+    }
+}
+
+/// How the versions of a pubspec are tagged.
+#[derive(Clone, Debug)]
+enum TagNaming {
+    /// `v1.2.3` or `1.2.3`; either one counts as tagged.
+    Prefix(TagPrefix),
+    /// A workspace package's `package_tag`, e.g. `core-v1.2.3`.
+    Package { name: String, format: String },
+}
+
+impl TagNaming {
+    fn tag(&self, v: &Version) -> String {
+        match self {
+            TagNaming::Prefix(prefix) => version_tag(v, *prefix),
+            TagNaming::Package { name, format } => format
+                .replace("{name}", name)
+                .replace("{version}", &version_tag(v, TagPrefix::None)),
+        }
+    }
+
+    /// Tag names that count as tagging `v`.
+    fn accepted(&self, v: &Version) -> Vec<String> {
+        match self {
+            TagNaming::Prefix(_) => vec![
+                version_tag(v, TagPrefix::None),
+                version_tag(v, TagPrefix::V),
+            ],
+            TagNaming::Package { .. } => vec![self.tag(v)],
+        }
+    }
+}
+
+/// Tag the current version if it isn't tagged yet, returning the created tag (only the name
+/// with `dry_run`).
+fn ensure_current_version_tag(
+    pubspec_path: &Path,
+    naming: &TagNaming,
+    dry_run: bool,
+) -> Result<Option<String>> {
+    let repo = match gix::discover(pubspec_dir(pubspec_path)) {
+        Ok(r) => r,
+        Err(_) => {
+            log!("[bump-version] Skipping tag check (not in a git repository)");
+            return Ok(None);
+        }
+    };
+
+    let content = fs::read_to_string(pubspec_path)
+        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
+
+    let version_str = match read_pubspec_version(&content) {
+        Some(v) => v,
+        None => {
+            log!("[bump-version] Skipping tag check (no version found in pubspec)");
+            return Ok(None);
+        }
+    };
+    let v = match Version::parse(&version_str) {
+        Ok(v) => v,
+        Err(e) => {
+            log!(
+                "[bump-version] Skipping tag check (invalid semver in pubspec '{}'): {}",
+                version_str,
+                e
+            );
+            return Ok(None);
+        }
+    };
+
+    let preferred_tag = naming.tag(&v);
+    let accepted = naming.accepted(&v);
+
+    // If any accepted name exists, do nothing.
+    for tag in &accepted {
+        if tag_exists(&repo, tag)? {
+            log!(
+                "[bump-version] Tag already exists for current version: {} (checked '{}')",
+                version_str,
+                accepted.join("' and '")
+            );
+            return Ok(None);
+        }
+    }
+
+    let head_id = match repo.head_id() {
+        Ok(id) => id.detach(),
+        Err(_) => {
+            log!("[bump-version] Skipping tag creation (repository has no commits yet)");
+            return Ok(None);
+        }
+    };
+
+    if dry_run {
+        return Ok(Some(preferred_tag));
+    }
+    repo.tag_reference(&preferred_tag, head_id, PreviousValue::MustNotExist)
+        .with_context(|| format!("Failed to create lightweight tag '{preferred_tag}'"))?;
+    log!(
+        "[bump-version] Created lightweight tag '{}' for current version {}",
+        preferred_tag,
+        version_str
+    );
+    Ok(Some(preferred_tag))
+}
+
+/// Conventional Commits header: type, optional scope, optional `!`.
+const CONVENTIONAL_HEADER: &str = r"^([A-Za-z]+)(\([^)]*\))?(!)?:\s";
+
+/// The release impact of a commit message, matched with `CONVENTIONAL_HEADER`.
+fn commit_impact(header_regex: &Regex, message: &str) -> Option<Impact> {
+    let mut lines = message.lines();
+    let caps = header_regex.captures(lines.next()?.trim())?;
+    let breaking_footer =
+        lines.any(|l| l.starts_with("BREAKING CHANGE:") || l.starts_with("BREAKING-CHANGE:"));
+    if caps.get(3).is_some() || breaking_footer {
+        return Some(Impact::Breaking);
+    }
+    match caps[1].to_ascii_lowercase().as_str() {
+        "feat" => Some(Impact::Minor),
+        "fix" | "perf" => Some(Impact::Patch),
+        _ => None,
+    }
+}
+
+/// Map an impact to a version part; 0.x versions shift everything down one level.
+fn impact_part(impact: Impact, current: &Version) -> VersionPart {
+    match (impact, current.major) {
+        (Impact::Breaking, 0) => VersionPart::Minor,
+        (Impact::Breaking, _) => VersionPart::Major,
+        (Impact::Minor, 0) => VersionPart::Patch,
+        (Impact::Minor, _) => VersionPart::Minor,
+        (Impact::Patch, _) => VersionPart::Patch,
+    }
+}
+
+/// Classify the commits reachable from HEAD but from no version tag, and return the
+/// part to bump (None when no commit calls for a release).
+fn auto_bump_part(pubspec_path: &Path, current: &Version) -> Result<Option<VersionPart>> {
+    let repo = gix::discover(pubspec_dir(pubspec_path)).context("auto requires a git repository")?;
+    let head_id = repo
+        .head_id()
+        .context("auto requires a repository with commits")?
+        .detach();
+
+    let mut tagged = Vec::new();
+    for reference in repo.references()?.tags()? {
+        let mut reference = reference.map_err(|e| anyhow::anyhow!(e))?;
+        let name = reference.name().shorten().to_string();
+        if Version::parse(name.strip_prefix('v').unwrap_or(&name)).is_err() {
+            continue;
+        }
+        if let Ok(commit) = reference.peel_to_commit() {
+            tagged.push(commit.id);
+        }
+    }
+    if tagged.is_empty() {
+        log!("[bump-version] No version tags found; considering the full history");
+    }
+
+    let header_regex = Regex::new(CONVENTIONAL_HEADER).unwrap();
+    let mut decided: Option<Impact> = None;
+    let mut deciding = Vec::new();
+    let mut count = 0;
+    for info in repo.rev_walk([head_id]).with_hidden(tagged).all()? {
+        let info = info?;
+        let commit = info.object()?;
+        let message = commit.message_raw_sloppy().to_string();
+        count += 1;
+        let Some(impact) = commit_impact(&header_regex, &message) else {
+            continue;
+        };
+        let summary = format!(
+            "{} {}",
+            info.id().shorten_or_id(),
+            message.lines().next().unwrap_or_default().trim()
+        );
+        if decided.is_none_or(|d| impact > d) {
+            decided = Some(impact);
+            deciding.clear();
+        }
+        if decided == Some(impact) {
+            deciding.push(summary);
+        }
+    }
+
+    let Some(impact) = decided else {
+        log!(
+            "[bump-version] No feat/fix/breaking commits in {} commit(s) since the last version tag; nothing to bump",
+            count
+        );
+        return Ok(None);
+    };
+    let part = impact_part(impact, current);
+    log!(
+        "[bump-version] {:?} bump from {} commit(s) since the last version tag, decided by:",
+        part, count
+    );
+    for line in &deciding {
+        log!("  {}", line);
+    }
+    Ok(Some(part))
+}
+
+fn auto_bump(opts: &BumpOptions) -> Result<()> {
+    if opts.selects_packages() {
+        anyhow::bail!(
+            "auto bumps a single pubspec; --package and --all-packages are not supported"
+        );
+    }
+    let pubspec_path = Path::new(&opts.pubspec);
+    let content = fs::read_to_string(pubspec_path)
+        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
+    let version_str = read_pubspec_version(&content)
+        .with_context(|| format!("No version found in {}", pubspec_path.display()))?;
+    let current = Version::parse(&version_str)
+        .with_context(|| format!("Invalid semver in pubspec '{}'", version_str))?;
+    match auto_bump_part(pubspec_path, &current)? {
+        Some(part) => do_bump(Bump::Part(part), opts),
+        None => report_bumps(opts, Vec::new()),
+    }
+}
+
+fn show_version(pubspec_path: &Path, format: ShowFormat) -> Result<()> {
+    let content = fs::read_to_string(pubspec_path)
+        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
+    let version_str = read_pubspec_version(&content)
+        .with_context(|| format!("No version found in {}", pubspec_path.display()))?;
+    let v = Version::parse(&version_str)
+        .with_context(|| format!("Invalid semver in pubspec '{}'", version_str))?;
+
+    // `name` is the version without build number, as passed to `flutter build --build-name`.
+    let mut name = format!("{}.{}.{}", v.major, v.minor, v.patch);
+    if !v.pre.is_empty() {
+        name = format!("{}-{}", name, v.pre);
+    }
+    let fields = [
+        ("version", v.to_string()),
+        ("name", name),
+        ("major", v.major.to_string()),
+        ("minor", v.minor.to_string()),
+        ("patch", v.patch.to_string()),
+        ("pre", v.pre.to_string()),
+        ("build", v.build.to_string()),
+    ];
+    match format {
+        ShowFormat::Text => {
+            for (key, value) in &fields {
+                println!("{}: {}", key, value);
+            }
+        }
+        ShowFormat::Json => {
+            let mut object = serde_json::Map::new();
+            for (key, value) in fields {
+                let value = match key {
+                    "major" | "minor" | "patch" => serde_json::json!(value.parse::<u64>()?),
+                    "pre" | "build" if value.is_empty() => serde_json::Value::Null,
+                    _ => serde_json::Value::String(value),
+                };
+                object.insert(key.to_string(), value);
+            }
+            println!("{}", serde_json::to_string_pretty(&object)?);
+        }
+        ShowFormat::Shell => {
+            // Semver only allows [0-9A-Za-z.+-], so values need no quoting.
+            for (key, value) in &fields {
+                match *key {
+                    "version" => println!("VERSION={}", value),
+                    _ => println!("VERSION_{}={}", key.to_ascii_uppercase(), value),
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Journal of recent bumps inside the git dir, so it is never committed.
+const JOURNAL_FILE: &str = "bump_version_journal.json";
+/// Number of bumps kept in the journal.
+const JOURNAL_LIMIT: usize = 20;
+
+/// One recorded bump, undone by `revert`.
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalEntry {
+    /// pubspec.yaml, relative to the repository root.
+    pubspec: String,
+    /// Extra version files written, relative to the repository root.
+    files: Vec<String>,
+    old_version: String,
+    new_version: String,
+    /// Tags created by the bump.
+    tags: Vec<String>,
+    /// Commit made by `release`.
+    commit: Option<String>,
+    /// Remote the release was pushed to.
+    pushed_to: Option<String>,
+    /// Constraints on the bumped package rewritten in other pubspecs.
+    #[serde(default)]
+    constraints: Vec<JournalConstraint>,
+}
+
+/// A dependency constraint rewritten by a workspace bump.
+#[derive(Debug, Serialize, Deserialize)]
+struct JournalConstraint {
+    /// pubspec.yaml of the dependent, relative to the repository root.
+    file: String,
+    #[serde(flatten)]
+    edit: ConstraintEdit,
+}
+
+fn journal_path(repo: &gix::Repository) -> PathBuf {
+    repo.git_dir().join(JOURNAL_FILE)
+}
+
+fn read_journal(repo: &gix::Repository) -> Result<Vec<JournalEntry>> {
+    let path = journal_path(repo);
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&content)
+        .with_context(|| format!("Invalid bump journal {}", path.display()))
+}
+
+fn write_journal(repo: &gix::Repository, journal: &[JournalEntry]) -> Result<()> {
+    let path = journal_path(repo);
+    let recent = &journal[journal.len().saturating_sub(JOURNAL_LIMIT)..];
+    let content = serde_json::to_string_pretty(recent)?;
+    fs::write(&path, format!("{}\n", content))
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Path of `path` relative to the repository root, with `/` separators.
+fn repo_relative(workdir: &Path, path: &Path) -> Result<gix::bstr::BString> {
+    let absolute = path
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve {}", path.display()))?;
+    let relative = absolute
+        .strip_prefix(workdir)
+        .with_context(|| format!("{} is outside the repository", path.display()))?;
+    Ok(gix::path::to_unix_separators_on_windows(gix::path::into_bstr(relative)).into_owned())
+}
+
+/// Record a written bump in the journal, along with the constraints it rewrote in other
+/// pubspecs; nothing is recorded outside a git repository.
+fn record_bump(
+    plan: &BumpPlan,
+    constraints: &[(PathBuf, ConstraintEdit)],
+    tags: Vec<String>,
+    commit: Option<gix::ObjectId>,
+    pushed_to: Option<&str>,
+) -> Result<()> {
+    let Ok(repo) = gix::discover(pubspec_dir(&plan.pubspec_path)) else {
+        return Ok(());
+    };
+    let Some(workdir) = repo.workdir() else {
+        return Ok(());
+    };
+    let workdir = workdir.canonicalize()?;
+    let mut files = Vec::new();
+    for (path, _) in plan.writes() {
+        files.push(repo_relative(&workdir, path)?.to_string());
+    }
+    let mut journal_constraints = Vec::new();
+    for (path, edit) in constraints {
+        journal_constraints.push(JournalConstraint {
+            file: repo_relative(&workdir, path)?.to_string(),
+            edit: edit.clone(),
+        });
+    }
+    let mut journal = read_journal(&repo)?;
+    journal.push(JournalEntry {
+        pubspec: files.remove(0),
+        files,
+        old_version: plan.current.to_string(),
+        new_version: plan.new_version.to_string(),
+        tags,
+        commit: commit.map(|id| id.to_string()),
+        pushed_to: pushed_to.map(str::to_string),
+        constraints: journal_constraints,
+    });
+    write_journal(&repo, &journal)
+}
+
+/// Version of the pubspec at `relative` in commit `id`.
+fn committed_version(
+    repo: &gix::Repository,
+    id: gix::ObjectId,
+    relative: &str,
+) -> Result<Option<String>> {
+    let tree = repo.find_commit(id)?.tree()?;
+    let Some(entry) = tree.lookup_entry_by_path(relative)? else {
+        return Ok(None);
+    };
+    let blob = entry.object()?;
+    Ok(read_pubspec_version(&String::from_utf8_lossy(&blob.data)))
+}
+
+/// The first-parent commit that moved the pubspec from `old` to `new`.
+fn find_bump_commit(
+    repo: &gix::Repository,
+    relative: &str,
+    old: &str,
+    new: &str,
+) -> Result<Option<gix::ObjectId>> {
+    let mut id = repo.head_id()?.detach();
+    loop {
+        let Some(parent) = repo.find_commit(id)?.parent_ids().next() else {
+            return Ok(None);
+        };
+        let parent = parent.detach();
+        match committed_version(repo, parent, relative)?.as_deref() {
+            Some(v) if v == old => return Ok(Some(id)),
+            Some(v) if v == new => id = parent,
+            _ => return Ok(None),
+        }
+    }
+}
+
+fn run_git(workdir: &Path, args: &[&str]) -> Result<()> {
+    let status = std::process::Command::new("git")
+        .current_dir(workdir)
+        .args(args)
+        .status()
+        .with_context(|| format!("Failed to run 'git {}'", args[0]))?;
+    if !status.success() {
+        anyhow::bail!("git {} failed ({})", args.join(" "), status);
+    }
+    Ok(())
+}
+
+/// Ask how to undo the bump commit; only asked on a terminal.
+fn ask_commit_action(short: &str, can_reset: bool) -> Result<CommitAction> {
+    use std::io::{BufRead, IsTerminal, Write};
+
+    if !std::io::stdin().is_terminal() {
+        anyhow::bail!(
+            "The bump was committed in {}; pass --commit {} to undo it",
+            short,
+            if can_reset {
+                "reset or --commit revert"
+            } else {
+                "revert"
+            }
+        );
+    }
+    if can_reset {
+        print!(
+            "[bump-version] The bump was committed in {} and is not pushed. [r]eset it, add a re[v]ert commit or [a]bort? ",
+            short
+        );
+    } else {
+        print!(
+            "[bump-version] The bump was committed in {}, which is pushed or not HEAD. Add a re[v]ert commit or [a]bort? ",
+            short
+        );
+    }
+    std::io::stdout().flush()?;
+    let mut answer = String::new();
+    std::io::stdin().lock().read_line(&mut answer)?;
+    match answer.trim() {
+        "r" | "reset" if can_reset => Ok(CommitAction::Reset),
+        "v" | "revert" => Ok(CommitAction::Revert),
+        _ => anyhow::bail!("Revert aborted"),
+    }
+}
+
+/// Reset or revert the commit that made the bump.
+fn undo_bump_commit(
+    repo: &gix::Repository,
+    workdir: &Path,
+    commit: gix::ObjectId,
+    entry: &JournalEntry,
+    action: Option<CommitAction>,
+) -> Result<()> {
+    let short = commit.to_hex_with_len(7).to_string();
+    let remote_branches = std::process::Command::new("git")
+        .current_dir(workdir)
+        .args(["branch", "-r", "--contains", &commit.to_string()])
+        .output()
+        .context("Failed to run 'git branch'")?;
+    let pushed = entry.pushed_to.is_some()
+        || !String::from_utf8_lossy(&remote_branches.stdout)
+            .trim()
+            .is_empty();
+    let is_head = repo.head_id()?.detach() == commit;
+    let action = match action {
+        Some(action) => action,
+        None => ask_commit_action(&short, is_head && !pushed)?,
+    };
+    match action {
+        CommitAction::Reset if pushed => anyhow::bail!(
+            "Bump commit {} is already pushed; use --commit revert",
+            short
+        ),
+        CommitAction::Reset if !is_head => anyhow::bail!(
+            "Bump commit {} is no longer HEAD; use --commit revert",
+            short
+        ),
+        CommitAction::Reset => {
+            run_git(workdir, &["reset", "--keep", &format!("{}^", commit)])?;
+            log!("[bump-version] Dropped bump commit {}", short);
+        }
+        CommitAction::Revert => {
+            run_git(workdir, &["revert", "--no-edit", &commit.to_string()])?;
+            log!("[bump-version] Reverted bump commit {}", short);
+        }
+    }
+    Ok(())
+}
+
+/// Undo the last journaled bump of `pubspec_path`: restore or un-commit its files and delete
+/// the tags it created. Uncommitted bumps stacked on top of each other are unwound together,
+/// newest first. Returns false when no recorded bump matches the pubspec anymore.
+fn revert_journaled(
+    repo: &gix::Repository,
+    pubspec_path: &Path,
+    commit_action: Option<CommitAction>,
+) -> Result<bool> {
+    let Some(workdir) = repo.workdir() else {
+        return Ok(false);
+    };
+    let workdir = workdir.canonicalize()?;
+    let relative = repo_relative(&workdir, pubspec_path)?.to_string();
+    let mut journal = read_journal(repo)?;
+    let Some(position) = journal.iter().rposition(|entry| entry.pubspec == relative) else {
+        return Ok(false);
+    };
+    let mut entries = vec![journal.remove(position)];
+
+    let content = fs::read_to_string(pubspec_path)
+        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
+    let current = read_pubspec_version(&content);
+    let head_version = match repo.head_id() {
+        Ok(head) => committed_version(repo, head.detach(), &relative)?,
+        Err(_) => None,
+    };
+    let entry = &entries[0];
+    let committed = match (current.as_deref(), head_version.as_deref()) {
+        (Some(c), Some(h)) if c == entry.new_version && h == entry.old_version => Some(false),
+        (Some(c), Some(h)) if c == entry.new_version && h == entry.new_version => Some(true),
+        (Some(c), Some(h)) if c == entry.new_version => {
+            stacked_bumps(&mut journal, &relative, &entry.old_version, h).map(|older| {
+                entries.extend(older);
+                false
+            })
+        }
+        _ => None,
+    };
+    let Some(committed) = committed else {
+        let entry = &entries[0];
+        log!(
+            "[bump-version] Ignoring recorded bump {} → {} ({} is at {})",
+            entry.old_version,
+            entry.new_version,
+            pubspec_path.display(),
+            current.as_deref().unwrap_or("<unknown>")
+        );
+        write_journal(repo, &journal)?;
+        return Ok(false);
+    };
+
+    if committed {
+        let entry = &entries[0];
+        let commit = match &entry.commit {
+            Some(id) => gix::ObjectId::from_hex(id.as_bytes())
+                .with_context(|| format!("Invalid commit '{}' in the bump journal", id))?,
+            None => find_bump_commit(repo, &relative, &entry.old_version, &entry.new_version)?
+                .with_context(|| {
+                    format!(
+                        "Can't find the commit that bumped {} to {}",
+                        entry.old_version, entry.new_version
+                    )
+                })?,
+        };
+        undo_bump_commit(repo, &workdir, commit, entry, commit_action)?;
+    } else {
+        // The files may hold other changes, so only the rewritten values go back.
+        for entry in &entries {
+            restore_versions(&workdir, pubspec_path, entry)?;
+            for constraint in &entry.constraints {
+                restore_constraint(&workdir, constraint)?;
+            }
+        }
+    }
+
+    for entry in &entries {
+        for tag in &entry.tags {
+            let Some(reference) = repo.try_find_reference(format!("refs/tags/{}", tag).as_str())?
+            else {
+                continue;
+            };
+            reference
+                .delete()
+                .with_context(|| format!("Failed to delete tag '{}'", tag))?;
+            log!("[bump-version] Deleted tag '{}'", tag);
+            if let Some(remote) = &entry.pushed_to {
+                log!(
+                    "[bump-version] '{}' was pushed; delete it there with `git push --delete {} {}`",
+                    tag, remote, tag
+                );
+            }
+        }
+        log!(
+            "[bump-version] Reverted: {} → {}",
+            entry.new_version,
+            entry.old_version
+        );
+    }
+    write_journal(repo, &journal)?;
+    Ok(true)
+}
+
+/// Remove and return, newest first, the uncommitted journal entries of `relative` that lead
+/// from the committed `head` version up to `old`; `None` if they don't form such a chain.
+fn stacked_bumps(
+    journal: &mut Vec<JournalEntry>,
+    relative: &str,
+    old: &str,
+    head: &str,
+) -> Option<Vec<JournalEntry>> {
+    let mut positions = Vec::new();
+    let mut old = old.to_string();
+    let mut end = journal.len();
+    while old != head {
+        let position = journal[..end]
+            .iter()
+            .rposition(|entry| entry.pubspec == relative)?;
+        let entry = &journal[position];
+        if entry.new_version != old || entry.commit.is_some() {
+            return None;
+        }
+        old = entry.old_version.clone();
+        positions.push(position);
+        end = position;
+    }
+    // Positions are descending, so earlier removals don't shift later ones.
+    Some(positions.into_iter().map(|p| journal.remove(p)).collect())
+}
+
+/// Put the old version back in the pubspec and the extra version files of `entry`, in each
+/// file only where it still holds the new one.
+fn restore_versions(workdir: &Path, pubspec_path: &Path, entry: &JournalEntry) -> Result<()> {
+    let content = fs::read_to_string(pubspec_path)
+        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
+    let config = read_bump_config(&content, pubspec_path)?;
+    let span = yaml_value_span(&content, "version");
+    restore_span(
+        pubspec_path,
+        content,
+        span,
+        &entry.new_version,
+        &entry.old_version,
+    )?;
+    if entry.files.is_empty() {
+        return Ok(());
+    }
+
+    let new = Version::parse(&entry.new_version).with_context(|| {
+        format!(
+            "Invalid version '{}' in the bump journal",
+            entry.new_version
+        )
+    })?;
+    let old = Version::parse(&entry.old_version).with_context(|| {
+        format!(
+            "Invalid version '{}' in the bump journal",
+            entry.old_version
+        )
+    })?;
+    for file in &entry.files {
+        let path = workdir.join(file);
+        let Some(version_file) = config.files.iter().find(|version_file| {
+            repo_relative(workdir, &pubspec_path.with_file_name(&version_file.file))
+                .is_ok_and(|relative| relative == file.as_str())
+        }) else {
+            log!(
+                "[bump-version] Left {} alone (no longer in bump_version.files)",
+                file
+            );
+            continue;
+        };
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let span = version_file_span(&content, &version_file.locator)?;
+        let value = version_file.value;
+        if restore_span(
+            &path,
+            content,
+            span,
+            &value.render(&new),
+            &value.render(&old),
+        )? {
+            log!("[bump-version] Restored {}", file);
+        }
+    }
+    Ok(())
+}
+
+/// Replace the value at `span` of `content` with `old` and write it to `path`, if it still
+/// reads `new`.
+fn restore_span(
+    path: &Path,
+    mut content: String,
+    span: Option<Range<usize>>,
+    new: &str,
+    old: &str,
+) -> Result<bool> {
+    match span {
+        Some(span) if content[span.clone()].trim() == new => {
+            content.replace_range(span, old);
+            fs::write(path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            Ok(true)
+        }
+        _ => {
+            log!(
+                "[bump-version] Left {} alone (no longer '{}')",
+                path.display(),
+                new
+            );
+            Ok(false)
+        }
+    }
+}
+
+/// Put a rewritten constraint back, unless it was changed again since.
+fn restore_constraint(workdir: &Path, constraint: &JournalConstraint) -> Result<()> {
+    let path = workdir.join(&constraint.file);
+    let mut content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let edit = &constraint.edit;
+    match yaml_value_span(&content, &edit.key) {
+        Some(span) if content[span.clone()].trim() == edit.new => {
+            content.replace_range(span, &edit.old);
+            fs::write(&path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            log!(
+                "[bump-version] Restored {} to '{}' in {}",
+                edit.key,
+                edit.old,
+                constraint.file
+            );
+        }
+        _ => log!(
+            "[bump-version] Left {} in {} alone (no longer '{}')",
+            edit.key,
+            constraint.file,
+            edit.new
+        ),
+    }
+    Ok(())
+}
+
+fn revert_bump(pubspec_path: &Path, commit_action: Option<CommitAction>) -> Result<()> {
+    if let Ok(repo) = gix::discover(pubspec_dir(pubspec_path)) {
+        if revert_journaled(&repo, pubspec_path, commit_action)? {
+            return Ok(());
+        }
+    }
+
+    let content = fs::read_to_string(pubspec_path)
+        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
+    let current_version = read_pubspec_version(&content)
+        .unwrap_or_else(|| "<unknown>".to_string());
+
+    let mut paths = vec![pubspec_path.to_path_buf()];
+    for entry in read_bump_config(&content, pubspec_path)?.files {
+        paths.push(pubspec_path.with_file_name(entry.file));
+    }
+
+    // Use git to find which files have uncommitted changes vs HEAD
+    let mut changed = Vec::new();
+    for path in &paths {
+        let diff_output = std::process::Command::new("git")
+            .args(["diff", "HEAD", "--name-only", "--"])
+            .arg(path)
+            .output()
+            .context("Failed to run 'git diff'")?;
+        if !String::from_utf8_lossy(&diff_output.stdout).trim().is_empty() {
+            changed.push(path);
+        }
+    }
+    if changed.is_empty() {
+        log!("[bump-version] Nothing to revert ({} has no changes vs HEAD)", pubspec_path.display());
+        return Ok(());
+    }
+
+    // Restore the changed files from HEAD
+    for path in &changed {
+        let status = std::process::Command::new("git")
+            .args(["checkout", "HEAD", "--"])
+            .arg(path)
+            .status()
+            .context("Failed to run 'git checkout'")?;
+
+        if !status.success() {
+            anyhow::bail!("git checkout HEAD -- {} failed", path.display());
+        }
+        if *path != pubspec_path {
+            log!("[bump-version] Restored {}", path.display());
+        }
+    }
+
+    let restored_content = fs::read_to_string(pubspec_path)
+        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
+    let restored_version = read_pubspec_version(&restored_content)
+        .unwrap_or_else(|| "<unknown>".to_string());
+
+    log!("[bump-version] Reverted: {} → {}", current_version, restored_version);
+    Ok(())
+}
+
+/// Span of the scalar after `key:` on a line (inside quotes when quoted), if any.
+fn line_scalar_span(line: &str, value_start: usize) -> Option<Range<usize>> {
+    let rest = &line[value_start..];
+    let lead = rest.len() - rest.trim_start().len();
+    let start = value_start + lead;
+    let rest = &line[start..];
+    match rest.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let end = rest[1..].find(quote)?;
+            Some(start + 1..start + 1 + end)
+        }
+        // A block scalar (`|`, `>-`, ...) continues on the following lines.
+        '#' | '|' | '>' => None,
+        _ => {
+            let end = rest.find(" #").unwrap_or(rest.len());
+            let value = rest[..end].trim_end();
+            (!value.is_empty()).then(|| start..start + value.len())
+        }
+    }
+}
+
+/// Iterate `(offset, line)` pairs, with the line ending stripped.
+fn lines_with_offsets(content: &str) -> impl Iterator<Item = (usize, &str)> {
+    content.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line.trim_end_matches(['\r', '\n'])))
+    })
+}
+
+/// Span of the scalar value at a dotted key path of YAML block mappings.
+fn yaml_value_span(content: &str, path: &str) -> Option<Range<usize>> {
+    let keys: Vec<&str> = path.split('.').collect();
+    // Indent of the keys at each matched level; None until the first child line is seen.
+    let mut levels: Vec<Option<usize>> = vec![Some(0)];
+    for (offset, line) in lines_with_offsets(content) {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("---") {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        while levels.len() > 1 && levels[levels.len() - 2].is_some_and(|i| indent <= i) {
+            levels.pop();
+        }
+        let level = levels.last_mut().unwrap();
+        match level {
+            None => *level = Some(indent),
+            Some(expected) if *expected != indent => continue,
+            Some(_) => {}
+        }
+        let key = keys[levels.len() - 1];
+        let Some(rest) = trimmed.strip_prefix(key) else {
+            continue;
+        };
+        if !rest.starts_with(':') {
+            continue;
+        }
+        if levels.len() == keys.len() {
+            let value_start = line.len() - rest.len() + 1;
+            return line_scalar_span(line, value_start).map(|r| offset + r.start..offset + r.end);
+        }
+        levels.push(None);
+    }
+    None
+}
+
+/// Span of the quoted string value of a dotted TOML key (`[table]` + `key`, or dotted keys).
+fn toml_value_span(content: &str, path: &str) -> Option<Range<usize>> {
+    let unquote = |part: &str| part.trim().trim_matches(['"', '\'']).to_string();
+    let target: Vec<String> = path.split('.').map(unquote).collect();
+    let mut table: Vec<String> = Vec::new();
+    for (offset, line) in lines_with_offsets(content) {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with('[') {
+            let header = trimmed.trim_start_matches('[');
+            let header = header.split(']').next().unwrap_or_default();
+            table = header.split('.').map(unquote).collect();
+            continue;
+        }
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        let Some((key, _)) = trimmed.split_once('=') else {
+            continue;
+        };
+        let mut full = table.clone();
+        full.extend(key.split('.').map(unquote));
+        if full == target {
+            let value_start = line.find('=')? + 1;
+            return quoted_span(line, value_start).map(|r| offset + r.start..offset + r.end);
+        }
+    }
+    None
+}
+
+/// Span of the quoted string value of a dotted Pkl property (`a { b = "..." }`).
+fn pkl_value_span(content: &str, path: &str) -> Option<Range<usize>> {
+    let target: Vec<&str> = path.split('.').collect();
+    let mut stack: Vec<String> = Vec::new();
+    for (offset, line) in lines_with_offsets(content) {
+        let code = line.split("//").next().unwrap_or_default();
+        let trimmed = code.trim();
+        for _ in trimmed.chars().take_while(|c| *c == '}') {
+            stack.pop();
+        }
+        let trimmed = trimmed.trim_start_matches('}').trim_start();
+        let name: String = trimmed
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+        if name.is_empty() {
+            continue;
+        }
+        if trimmed.ends_with('{') {
+            stack.push(name);
+            continue;
+        }
+        let after_name = trimmed[name.len()..].trim_start();
+        // `name = "..."` or `name: String = "..."`
+        if !(after_name.starts_with('=') || after_name.starts_with(':')) {
+            continue;
+        }
+        if stack.len() + 1 == target.len()
+            && stack.iter().zip(&target).all(|(a, b)| a == b)
+            && name == target[target.len() - 1]
+        {
+            let value_start = line.find('=')? + 1;
+            return quoted_span(line, value_start).map(|r| offset + r.start..offset + r.end);
+        }
+    }
+    None
+}
+
+/// Span inside the quotes of the string starting after `value_start`.
+fn quoted_span(line: &str, value_start: usize) -> Option<Range<usize>> {
+    line_scalar_span(line, value_start).filter(|r| {
+        r.start > 0 && matches!(line.as_bytes()[r.start - 1], b'"' | b'\'')
+    })
+}
+
+/// Minimal JSON walker that finds the span of a string value by JSON pointer, so the
+/// rest of the document keeps its formatting.
+struct JsonScanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl JsonScanner<'_> {
+    fn skip_ws(&mut self) {
+        while self.pos < self.bytes.len() && self.bytes[self.pos].is_ascii_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.pos).copied()
+    }
+
+    /// Consume a string at `pos` and return the span of its raw contents.
+    fn string(&mut self) -> Option<Range<usize>> {
+        if self.peek()? != b'"' {
+            return None;
+        }
+        let start = self.pos + 1;
+        let mut i = start;
+        while i < self.bytes.len() {
+            match self.bytes[i] {
+                b'\\' => i += 2,
+                b'"' => {
+                    self.pos = i + 1;
+                    return Some(start..i);
+                }
+                _ => i += 1,
+            }
+        }
+        None
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        self.skip_ws();
+        match self.peek()? {
+            b'"' => {
+                self.string()?;
+            }
+            open @ (b'{' | b'[') => {
+                let close = if open == b'{' { b'}' } else { b']' };
+                self.pos += 1;
+                loop {
+                    self.skip_ws();
+                    if self.peek()? == close {
+                        self.pos += 1;
+                        break;
+                    }
+                    if open == b'{' {
+                        self.string()?;
+                        self.skip_ws();
+                        if self.peek()? != b':' {
+                            return None;
+                        }
+                        self.pos += 1;
+                    }
+                    self.skip_value()?;
+                    self.skip_ws();
+                    if self.peek()? == b',' {
+                        self.pos += 1;
+                    }
+                }
+            }
+            _ => {
+                while self
+                    .peek()
+                    .is_some_and(|b| !matches!(b, b',' | b'}' | b']') && !b.is_ascii_whitespace())
+                {
+                    self.pos += 1;
+                }
+            }
+        }
+        Some(())
+    }
+
+    fn find(&mut self, path: &[String]) -> Option<Range<usize>> {
+        self.skip_ws();
+        let Some((head, rest)) = path.split_first() else {
+            return self.string();
+        };
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                loop {
+                    self.skip_ws();
+                    if self.peek()? == b'}' {
+                        return None;
+                    }
+                    let key = self.string()?;
+                    self.skip_ws();
+                    if self.peek()? != b':' {
+                        return None;
+                    }
+                    self.pos += 1;
+                    if &self.bytes[key] == head.as_bytes() {
+                        return self.find(rest);
+                    }
+                    self.skip_value()?;
+                    self.skip_ws();
+                    if self.peek()? == b',' {
+                        self.pos += 1;
+                    }
+                }
+            }
+            b'[' => {
+                let index: usize = head.parse().ok()?;
+                self.pos += 1;
+                for _ in 0..index {
+                    self.skip_value()?;
+                    self.skip_ws();
+                    if self.peek()? != b',' {
+                        return None;
+                    }
+                    self.pos += 1;
+                }
+                self.find(rest)
+            }
+            _ => None,
+        }
+    }
+}
+
+fn json_value_span(content: &str, pointer: &str) -> Option<Range<usize>> {
+    let path: Vec<String> = pointer
+        .split('/')
+        .skip(1)
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect();
+    JsonScanner { bytes: content.as_bytes(), pos: 0 }.find(&path)
+}
+
+fn regex_value_span(content: &str, pattern: &str) -> Result<Option<Range<usize>>> {
+    let regex = regex::RegexBuilder::new(pattern)
+        .multi_line(true)
+        .build()
+        .with_context(|| format!("Invalid regex '{}'", pattern))?;
+    let Some(caps) = regex.captures(content) else {
+        return Ok(None);
+    };
+    let group = caps
+        .name("version")
+        .or_else(|| caps.get(1))
+        .with_context(|| format!("Regex '{}' has no capture group for the version", pattern))?;
+    Ok(Some(group.range()))
+}
+
+/// Compute the updated contents of every extra version file, failing before anything is
+/// written if a locator doesn't match.
+fn plan_version_files(
+    pubspec_path: &Path,
+    config: &BumpConfig,
+    new_version: &Version,
+) -> Result<Vec<(PathBuf, String)>> {
+    let mut updates = Vec::new();
+    for entry in &config.files {
+        let path = pubspec_path.with_file_name(&entry.file);
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let span = version_file_span(&content, &entry.locator)?.with_context(|| {
+            format!("{} not found in {}", entry.locator, path.display())
+        })?;
+        let value = entry.value.render(new_version);
+        let updated = format!("{}{}{}", &content[..span.start], value, &content[span.end..]);
+        updates.push((path, updated));
+    }
+    Ok(updates)
+}
+
+/// Span of the version a locator points at in `content`.
+fn version_file_span(content: &str, locator: &Locator) -> Result<Option<Range<usize>>> {
+    Ok(match locator {
+        Locator::Yaml(key) => yaml_value_span(content, key),
+        Locator::Json(pointer) => json_value_span(content, pointer),
+        Locator::Toml(key) => toml_value_span(content, key),
+        Locator::Pkl(property) => pkl_value_span(content, property),
+        Locator::Regex(pattern) => regex_value_span(content, pattern)?,
+    })
+}
+
+/// Numeric build number (Flutter standard); 0 if missing or not numeric.
+fn build_number(v: &Version) -> u64 {
+    if v.build.is_empty() {
+        0
+    } else {
+        v.build.as_str().parse().unwrap_or(0)
+    }
+}
+
+fn set_build(v: &mut Version, build: u64) {
+    v.build = BuildMetadata::new(&build.to_string()).unwrap();
+}
+
+/// Bump a core part: lower parts reset to 0, prerelease cleared, build reset to 1.
+fn bump_core(v: &mut Version, part: VersionPart) {
+    match part {
+        VersionPart::Major => {
+            v.major += 1;
+            v.minor = 0;
+            v.patch = 0;
+        }
+        VersionPart::Minor => {
+            v.minor += 1;
+            v.patch = 0;
+        }
+        VersionPart::Patch => {
+            v.patch += 1;
+        }
+        VersionPart::Build => unreachable!("build is not a core part"),
+    }
+    v.pre = Prerelease::EMPTY;
+    set_build(v, 1);
+}
+
+/// Split a prerelease like `beta.2` into its label and number.
+fn split_prerelease(pre: &Prerelease) -> (&str, Option<u64>) {
+    match pre.as_str().split_once('.') {
+        Some((label, number)) => (label, number.parse().ok()),
+        None => (pre.as_str(), None),
+    }
+}
+
+fn next_version(current: &Version, bump: Bump) -> Result<Version> {
+    let mut v = current.clone();
+    match bump {
+        Bump::Part(VersionPart::Build) => {
+            let new_build = build_number(&v) + 1;
+            set_build(&mut v, new_build);
+        }
+        Bump::Part(part) => bump_core(&mut v, part),
+        Bump::Pre { channel, part } => {
+            if part == Some(VersionPart::Build) {
+                anyhow::bail!("--bump must be major, minor or patch");
+            }
+            let number = match part {
+                Some(part) => {
+                    bump_core(&mut v, part);
+                    1
+                }
+                // Starting from a release: the prerelease must precede the *next* version.
+                None if v.pre.is_empty() => {
+                    bump_core(&mut v, VersionPart::Patch);
+                    1
+                }
+                None => {
+                    let new_build = build_number(&v) + 1;
+                    set_build(&mut v, new_build);
+                    match split_prerelease(&v.pre) {
+                        (label, Some(n)) if label == channel.as_str() => n + 1,
+                        _ => 1,
+                    }
+                }
+            };
+            v.pre = Prerelease::new(&format!("{}.{}", channel.as_str(), number))?;
+            if v.cmp_precedence(current).is_le() {
+                anyhow::bail!(
+                    "{} would not be newer than {}; pass --bump to move to the next version",
+                    v,
+                    current
+                );
+            }
+        }
+        Bump::Set(target) => {
+            let same_release = target.major == v.major
+                && target.minor == v.minor
+                && target.patch == v.patch
+                && target.pre == v.pre;
+            let explicit_build = !target.build.is_empty();
+            if target == *current || (same_release && !explicit_build) {
+                anyhow::bail!("Version is already {} (use `build` for a new build number)", current);
+            }
+            if target.cmp_precedence(current).is_lt() {
+                log!(
+                    "[bump-version] Warning: {} is lower than the current version {}",
+                    target, current
+                );
+            }
+            v = target;
+            if !explicit_build {
+                set_build(&mut v, 1);
+            }
+        }
+        Bump::Promote => {
+            if v.pre.is_empty() {
+                anyhow::bail!("{} is not a prerelease; nothing to promote", current);
+            }
+            v.pre = Prerelease::EMPTY;
+            let new_build = build_number(&v) + 1;
+            set_build(&mut v, new_build);
+        }
+    }
+    Ok(v)
+}
+
+/// Days since 1970-01-01 → (year, month, day), proleptic Gregorian.
+fn civil_from_days(days: i64) -> (i64, u64, u64) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u64;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u64;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// YYMMDDNN for today (UTC), continuing NN when `previous` is from today.
+fn date_build_number(previous: u64) -> Result<u64> {
+    let days = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)?
+        .as_secs()
+        / 86_400;
+    day_build_number(days as i64, previous)
+}
+
+/// YYMMDDNN for the day `days` after 1970-01-01.
+fn day_build_number(days: i64, previous: u64) -> Result<u64> {
+    let (year, month, day) = civil_from_days(days);
+    let today = (year as u64 % 100) * 10_000 + month * 100 + day;
+    let sequence = if previous / 100 == today { previous % 100 + 1 } else { 1 };
+    if sequence > 99 {
+        anyhow::bail!("The date build strategy allows 99 builds per day (YYMMDDNN)");
+    }
+    Ok(today * 100 + sequence)
+}
+
+fn commit_count(pubspec_path: &Path) -> Result<u64> {
+    let repo = gix::discover(pubspec_dir(pubspec_path))
+        .context("The commit-count build strategy requires a git repository")?;
+    let head_id = repo
+        .head_id()
+        .context("The commit-count build strategy requires a repository with commits")?;
+    let mut count = 0;
+    for info in repo.rev_walk([head_id]).all()? {
+        info?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// Replace the build number chosen by the bump with the strategy's, making sure it
+/// never goes below the previous build number.
+fn apply_build_strategy(
+    new: &mut Version,
+    current: &Version,
+    strategy: &BuildStrategy,
+    pubspec_path: &Path,
+) -> Result<()> {
+    let previous = build_number(current);
+    let build = match strategy {
+        BuildStrategy::Reset => {
+            let build = build_number(new);
+            if build < previous {
+                log!(
+                    "[bump-version] Build number reset from {} to {}; app stores reject a lower build number (see --build-strategy)",
+                    previous, build
+                );
+            }
+            return Ok(());
+        }
+        BuildStrategy::Omit => {
+            new.build = BuildMetadata::EMPTY;
+            return Ok(());
+        }
+        BuildStrategy::Monotonic => previous + 1,
+        BuildStrategy::CommitCount => commit_count(pubspec_path)?,
+        BuildStrategy::Date => date_build_number(previous)?,
+        BuildStrategy::Env(var) => {
+            let value = std::env::var(var)
+                .with_context(|| format!("Build strategy env:{}: variable is not set", var))?;
+            value.trim().parse().with_context(|| {
+                format!("Build strategy env:{}: '{}' is not a number", var, value)
+            })?
+        }
+    };
+    if build <= previous {
+        anyhow::bail!(
+            "Build strategy {} gives build number {}, which does not exceed the previous {}",
+            strategy,
+            build,
+            previous
+        );
+    }
+    set_build(new, build);
+    Ok(())
+}
+
+/// A computed bump: the old and new versions and the new contents of every file it touches.
+struct BumpPlan {
+    pubspec_path: PathBuf,
+    current: Version,
+    new_version: Version,
+    pubspec: String,
+    /// Extra version files from the `bump_version:` section.
+    files: Vec<(PathBuf, String)>,
+}
+
+/// One bump as reported by `--json`.
+#[derive(Debug, Serialize)]
+struct BumpSummary {
+    /// Workspace package name; null outside `--package`/`--all-packages`.
+    package: Option<String>,
+    pubspec: String,
+    old_version: String,
+    new_version: String,
+    /// Tags created (or, with `--dry-run`, to be created).
+    tags: Vec<String>,
+    /// Files written (or to be written), pubspec.yaml first.
+    files: Vec<String>,
+}
+
+/// The `--json` output: `bumps` is empty when nothing was bumped.
+#[derive(Debug, Serialize)]
+struct BumpReport {
+    dry_run: bool,
+    bumps: Vec<BumpSummary>,
+}
+
+/// Print the bumps as JSON with `--json`, or what would change with `--dry-run`.
+fn report_bumps(opts: &BumpOptions, bumps: Vec<BumpSummary>) -> Result<()> {
+    if opts.json {
+        let report = BumpReport {
+            dry_run: opts.dry_run,
+            bumps,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else if opts.dry_run {
+        for bump in &bumps {
+            let package = bump
+                .package
+                .as_ref()
+                .map(|name| format!("{} ", name))
+                .unwrap_or_default();
+            log!(
+                "[bump-version] Dry run: would bump {}{} → {}",
+                package,
+                bump.old_version,
+                bump.new_version
+            );
+            for tag in &bump.tags {
+                log!("  tag '{}' (current version)", tag);
+            }
+            for file in &bump.files {
+                log!("  update {}", file);
+            }
+        }
+    }
+    Ok(())
+}
+
+impl BumpPlan {
+    fn summary(
+        &self,
+        package: Option<&str>,
+        dependents: &[PathBuf],
+        tags: Vec<String>,
+    ) -> BumpSummary {
+        let paths = self.writes().map(|(path, _)| path);
+        BumpSummary {
+            package: package.map(str::to_string),
+            pubspec: self.pubspec_path.display().to_string(),
+            old_version: self.current.to_string(),
+            new_version: self.new_version.to_string(),
+            tags,
+            files: paths
+                .chain(dependents.iter().map(PathBuf::as_path))
+                .map(|path| path.display().to_string())
+                .collect(),
+        }
+    }
+
+    /// Every file the bump writes, pubspec.yaml first.
+    fn writes(&self) -> impl Iterator<Item = (&Path, &str)> {
+        std::iter::once((self.pubspec_path.as_path(), self.pubspec.as_str())).chain(
+            self.files
+                .iter()
+                .map(|(path, content)| (path.as_path(), content.as_str())),
+        )
+    }
+}
+
+/// Compute a bump without writing anything; `None` if the pubspec has no version line.
+fn plan_bump(bump: Bump, opts: &BumpOptions) -> Result<Option<BumpPlan>> {
+    let pubspec_path = Path::new(&opts.pubspec);
+    let content = fs::read_to_string(pubspec_path)
+        .with_context(|| format!("Failed to read {}", pubspec_path.display()))?;
+
+    let Some((span, v)) = locate_pubspec_version(&content, pubspec_path)? else {
+        log!("No version line found in {}", pubspec_path.display());
+        return Ok(None);
+    };
+    // An explicit `set x.y.z+N` keeps its build number.
+    let explicit_build = matches!(&bump, Bump::Set(target) if !target.build.is_empty());
+    let mut new_version = next_version(&v, bump)?;
+    if !explicit_build {
+        apply_build_strategy(&mut new_version, &v, &opts.build_strategy, pubspec_path)?;
+    }
+
+    // Only the value changes, so quotes, comments and line endings are kept.
+    let new_content = format!(
+        "{}{}{}",
+        &content[..span.start],
+        new_version,
+        &content[span.end..]
+    );
+    let config = read_bump_config(&content, pubspec_path)?;
+    let files = plan_version_files(pubspec_path, &config, &new_version)?;
+    Ok(Some(BumpPlan {
+        pubspec_path: pubspec_path.to_path_buf(),
+        current: v,
+        new_version,
+        pubspec: new_content,
+        files,
+    }))
+}
+
+fn write_bump(plan: &BumpPlan) -> Result<()> {
+    fs::write(&plan.pubspec_path, &plan.pubspec)?;
+    log!("Bumped version to: {}", plan.new_version);
+    for (path, updated) in &plan.files {
+        fs::write(path, updated).with_context(|| format!("Failed to write {}", path.display()))?;
+        log!("[bump-version] Updated {}", path.display());
+    }
+    Ok(())
+}
+
+/// The root pubspec (when it has a name) and the packages matched by `bump_version.packages`
+/// globs, or else by the `workspace:` list.
+fn workspace_packages(root_path: &Path) -> Result<Vec<Package>> {
+    let content = fs::read_to_string(root_path)
+        .with_context(|| format!("Failed to read {}", root_path.display()))?;
+    let root: PubspecPackage = serde_yaml::from_str(&content)
+        .with_context(|| format!("Failed to parse {}", root_path.display()))?;
+    let config = read_bump_config(&content, root_path)?;
+    let patterns = if config.packages.is_empty() {
+        &root.workspace
+    } else {
+        &config.packages
+    };
+    if patterns.is_empty() {
+        anyhow::bail!(
+            "{} lists no packages (add a `workspace:` list or bump_version.packages globs)",
+            root_path.display()
+        );
+    }
+
+    let mut packages = Vec::new();
+    if let Some(name) = root.name {
+        packages.push(Package {
+            name,
+            pubspec_path: root_path.to_path_buf(),
+            version: read_pubspec_version(&content),
+            root: true,
+        });
+    }
+    for pattern in patterns {
+        let pattern_path = root_path.with_file_name(pattern).join("pubspec.yaml");
+        let matches = glob::glob(&pattern_path.to_string_lossy())
+            .with_context(|| format!("Invalid package glob '{}'", pattern))?;
+        for path in matches {
+            let path = path?;
+            if packages.iter().any(|p| p.pubspec_path == path) {
+                continue;
+            }
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let package: PubspecPackage = serde_yaml::from_str(&content)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            let name = package
+                .name
+                .with_context(|| format!("No package name in {}", path.display()))?;
+            packages.push(Package {
+                name,
+                version: read_pubspec_version(&content),
+                pubspec_path: path,
+                root: false,
+            });
+        }
+    }
+    Ok(packages)
+}
+
+/// Pubspec sections that declare dependencies.
+const DEPENDENCY_SECTIONS: &[&str] = &["dependencies", "dev_dependencies", "dependency_overrides"];
+
+/// A constraint value rewritten at a dotted pubspec key.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct ConstraintEdit {
+    key: String,
+    old: String,
+    new: String,
+}
+
+/// Point the version constraints on `dependency` at `version`: `^x.y.z` and exact versions are
+/// rewritten, other ranges are reported and left alone. Returns the edits made to `content`.
+fn update_dependency(
+    content: &mut String,
+    pubspec_path: &Path,
+    dependency: &str,
+    version: &Version,
+) -> Vec<ConstraintEdit> {
+    let mut name = version.clone();
+    name.build = BuildMetadata::EMPTY;
+    let mut edits = Vec::new();
+    for section in DEPENDENCY_SECTIONS {
+        // `core: ^1.2.0`, or a `version:` key next to `path:`/`hosted:`.
+        let mut key = format!("{}.{}", section, dependency);
+        let span = match yaml_value_span(content, &key) {
+            Some(span) => span,
+            None => {
+                key.push_str(".version");
+                match yaml_value_span(content, &key) {
+                    Some(span) => span,
+                    None => continue,
+                }
+            }
+        };
+        let constraint = content[span.clone()].trim();
+        let updated = if constraint.starts_with('^') {
+            format!("^{}", name)
+        } else if Version::parse(constraint).is_ok() {
+            name.to_string()
+        } else {
+            if constraint != "any" {
+                log!(
+                    "[bump-version] Left constraint '{}' on {} in {} unchanged",
+                    constraint,
+                    dependency,
+                    pubspec_path.display()
+                );
+            }
+            continue;
+        };
+        if updated != constraint {
+            edits.push(ConstraintEdit {
+                key,
+                old: constraint.to_string(),
+                new: updated.clone(),
+            });
+            content.replace_range(span, &updated);
+        }
+    }
+    edits
+}
+
+/// Bump the selected workspace packages and update the constraints of their dependents.
+/// Every change is computed before anything is written.
+fn bump_workspace(bump: Bump, opts: &BumpOptions) -> Result<()> {
+    let root_path = Path::new(&opts.pubspec);
+    let packages = workspace_packages(root_path)?;
+    let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+    log!("[bump-version] Workspace packages: {}", names.join(", "));
+
+    let selected: Vec<&Package> = if opts.all_packages {
+        packages.iter().filter(|p| p.version.is_some()).collect()
+    } else {
+        let mut selected = Vec::new();
+        for name in &opts.packages {
+            let package = packages.iter().find(|p| p.name == *name).with_context(|| {
+                format!(
+                    "Unknown package '{}' (available: {})",
+                    name,
+                    names.join(", ")
+                )
+            })?;
+            if package.version.is_none() {
+                anyhow::bail!("Package '{}' has no version", name);
+            }
+            selected.push(package);
+        }
+        selected
+    };
+
+    let content = fs::read_to_string(root_path)
+        .with_context(|| format!("Failed to read {}", root_path.display()))?;
+    let format = read_bump_config(&content, root_path)?
+        .package_tag
+        .unwrap_or_else(|| DEFAULT_PACKAGE_TAG.to_string());
+
+    let mut plans = Vec::new();
+    for package in &selected {
+        let mut package_opts = BumpOptions {
+            pubspec: package.pubspec_path.to_string_lossy().into_owned(),
+            ..opts.clone()
+        };
+        // Libraries usually carry no build number; don't give them one.
+        let unbuilt = package
+            .version
+            .as_deref()
+            .and_then(|v| Version::parse(v).ok())
+            .is_some_and(|v| v.build.is_empty());
+        if unbuilt && !matches!(bump, Bump::Part(VersionPart::Build)) {
+            package_opts.build_strategy = BuildStrategy::Omit;
+        }
+        if let Some(plan) = plan_bump(bump.clone(), &package_opts)? {
+            plans.push((*package, plan));
+        }
+    }
+
+    // Final contents by path: a pubspec can be both bumped and a dependent.
+    let mut writes: Vec<(PathBuf, String)> = Vec::new();
+    for (_, plan) in &plans {
+        for (path, content) in plan.writes() {
+            match writes.iter_mut().find(|(p, _)| p == path) {
+                Some(write) => write.1 = content.to_string(),
+                None => writes.push((path.to_path_buf(), content.to_string())),
+            }
+        }
+    }
+    let mut dependents: Vec<Vec<PathBuf>> = vec![Vec::new(); plans.len()];
+    let mut constraints: Vec<Vec<(PathBuf, ConstraintEdit)>> = vec![Vec::new(); plans.len()];
+    for package in &packages {
+        let path = &package.pubspec_path;
+        let mut content = match writes.iter().find(|(p, _)| p == path) {
+            Some((_, content)) => content.clone(),
+            None => fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?,
+        };
+        let mut changed = false;
+        for (i, (bumped, plan)) in plans.iter().enumerate() {
+            if bumped.name == package.name {
+                continue;
+            }
+            let edits = update_dependency(&mut content, path, &bumped.name, &plan.new_version);
+            if !edits.is_empty() {
+                dependents[i].push(path.clone());
+                constraints[i].extend(edits.into_iter().map(|edit| (path.clone(), edit)));
+                changed = true;
+            }
+        }
+        if changed {
+            match writes.iter_mut().find(|(p, _)| p == path) {
+                Some(write) => write.1 = content,
+                None => writes.push((path.clone(), content)),
+            }
+        }
+    }
+
+    // Ensure the current versions are tagged before bumping.
+    let mut created_tags = Vec::new();
+    for (package, _) in &plans {
+        let naming = if package.root {
+            TagNaming::Prefix(opts.tag_prefix)
+        } else {
+            TagNaming::Package {
+                name: package.name.clone(),
+                format: format.clone(),
+            }
+        };
+        created_tags.push(ensure_current_version_tag(
+            &package.pubspec_path,
+            &naming,
+            opts.dry_run,
+        )?);
+    }
+
+    let summaries = plans
+        .iter()
+        .zip(&dependents)
+        .zip(&created_tags)
+        .map(|(((package, plan), dependents), tag)| {
+            plan.summary(
+                Some(&package.name),
+                dependents,
+                tag.iter().cloned().collect(),
+            )
+        })
+        .collect();
+    if opts.dry_run {
+        return report_bumps(opts, summaries);
+    }
+
+    for (path, content) in &writes {
+        fs::write(path, content).with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    for (package, plan) in &plans {
+        log!(
+            "Bumped {} to: {} ({})",
+            package.name,
+            plan.new_version,
+            plan.pubspec_path.display()
+        );
+    }
+    let bumped_paths: Vec<&Path> = plans
+        .iter()
+        .map(|(_, plan)| plan.pubspec_path.as_path())
+        .collect();
+    for (path, _) in &writes {
+        if !bumped_paths.contains(&path.as_path()) {
+            log!("[bump-version] Updated {}", path.display());
+        }
+    }
+    for (((_, plan), constraints), tag) in plans.iter().zip(&constraints).zip(created_tags) {
+        record_bump(plan, constraints, tag.into_iter().collect(), None, None)?;
+    }
+    report_bumps(opts, summaries)
+}
+
+fn do_bump(bump: Bump, opts: &BumpOptions) -> Result<()> {
+    if opts.selects_packages() {
+        return bump_workspace(bump, opts);
+    }
+    let pubspec_path = Path::new(&opts.pubspec);
+    // Validate the bump before anything is tagged or written.
+    let Some(plan) = plan_bump(bump, opts)? else {
+        return report_bumps(opts, Vec::new());
+    };
+    // Ensure the current version is tagged before bumping.
+    let naming = TagNaming::Prefix(opts.tag_prefix);
+    let created_tag = ensure_current_version_tag(pubspec_path, &naming, opts.dry_run)?;
+    let tags: Vec<String> = created_tag.into_iter().collect();
+    if !opts.dry_run {
+        write_bump(&plan)?;
+        record_bump(&plan, &[], tags.clone(), None, None)?;
+    }
+    report_bumps(opts, vec![plan.summary(None, &[], tags)])
+}
+
+/// What `release` has changed so far, undone in reverse order when a later step fails.
+#[derive(Default)]
+struct ReleaseUndo {
+    /// Original contents of the rewritten files.
+    files: Vec<(PathBuf, String)>,
+    /// Original blob ids of the updated index entries.
+    index: Vec<(gix::bstr::BString, gix::ObjectId)>,
+    /// Branch moved by the release commit: (branch, previous commit, release commit).
+    commit: Option<(gix::refs::FullName, gix::ObjectId, gix::ObjectId)>,
+    tags: Vec<String>,
+}
+
+impl ReleaseUndo {
+    fn rollback(&self, repo: &gix::Repository) -> Result<()> {
+        for tag in self.tags.iter().rev() {
+            repo.find_reference(format!("refs/tags/{}", tag).as_str())?
+                .delete()
+                .with_context(|| format!("Failed to delete tag '{}'", tag))?;
+            log!("[bump-version] Deleted tag '{}'", tag);
+        }
+        if let Some((branch, previous, created)) = &self.commit {
+            repo.reference(
+                branch.clone(),
+                *previous,
+                PreviousValue::MustExistAndMatch(gix::refs::Target::Object(*created)),
+                "release: roll back",
+            )
+            .with_context(|| format!("Failed to reset {} to {}", branch.as_bstr(), previous))?;
+            log!(
+                "[bump-version] Reset {} to {}",
+                branch.shorten(),
+                previous.to_hex_with_len(7)
+            );
+        }
+        if !self.index.is_empty() {
+            let mut index = repo.open_index()?;
+            for (path, id) in &self.index {
+                if let Some(entry) =
+                    index.entry_mut_by_path_and_stage(path.as_ref(), Stage::Unconflicted)
+                {
+                    entry.id = *id;
+                }
+            }
+            index.remove_tree();
+            index
+                .write(Default::default())
+                .context("Failed to restore the git index")?;
+        }
+        for (path, content) in self.files.iter().rev() {
+            fs::write(path, content)
+                .with_context(|| format!("Failed to restore {}", path.display()))?;
+            log!("[bump-version] Restored {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// Bump `part`, commit the changed files on the current branch, tag the new version and
+/// optionally push, rolling back every step taken when a later one fails.
+fn release_version(part: VersionPart, opts: &BumpOptions, release: &ReleaseOptions) -> Result<()> {
+    if opts.selects_packages() {
+        anyhow::bail!(
+            "release works on a single pubspec; --package and --all-packages are not supported"
+        );
+    }
+    let pubspec_path = Path::new(&opts.pubspec);
+    let repo = gix::discover(pubspec_dir(pubspec_path))
+        .context("release must run inside a git repository")?;
+    let workdir = repo
+        .workdir()
+        .context("release needs a repository with a working tree")?
+        .canonicalize()?;
+
+    let branch = repo
+        .head_name()?
+        .context("HEAD is detached; check out a release branch first")?;
+    let branch_name = branch.shorten().to_string();
+    if !release.allow_branches.contains(&branch_name) {
+        anyhow::bail!(
+            "On branch '{}'; releases are made from {} (see --allow-branch)",
+            branch_name,
+            release.allow_branches.join(", ")
+        );
+    }
+    if repo.is_dirty()? {
+        anyhow::bail!(
+            "Working tree has uncommitted changes; commit or stash them before releasing"
+        );
+    }
+    let head = repo
+        .head_commit()
+        .context("Repository has no commits yet")?;
+
+    let plan = plan_bump(Bump::Part(part), opts)?
+        .with_context(|| format!("No version found in {}", pubspec_path.display()))?;
+    let tag = version_tag(&plan.new_version, opts.tag_prefix);
+    if tag_exists(&repo, &tag)? {
+        anyhow::bail!("Tag '{}' already exists", tag);
+    }
+
+    // Repository-relative paths of the files the bump writes; all must be tracked.
+    let index = repo.open_index()?;
+    let mut files = Vec::new();
+    for (path, content) in plan.writes() {
+        let relative = repo_relative(&workdir, path)?;
+        if index.entry_by_path(relative.as_ref()).is_none() {
+            anyhow::bail!(
+                "{} is not tracked by git; commit it before releasing",
+                path.display()
+            );
+        }
+        files.push((path, relative, content));
+    }
+    drop(index);
+
+    let message = format!("bump version to {}", plan.new_version);
+    if opts.dry_run {
+        let naming = TagNaming::Prefix(opts.tag_prefix);
+        let mut tags: Vec<String> = ensure_current_version_tag(pubspec_path, &naming, true)?
+            .into_iter()
+            .collect();
+        tags.push(tag);
+        if opts.json {
+            return report_bumps(opts, vec![plan.summary(None, &[], tags)]);
+        }
+        log!(
+            "[bump-version] Dry run: would release {} → {} on '{}'",
+            plan.current,
+            plan.new_version,
+            branch_name
+        );
+        for (path, _, _) in &files {
+            log!("  update {}", path.display());
+        }
+        log!("  commit \"{}\"", message);
+        for tag in &tags {
+            log!("  tag '{}'", tag);
+        }
+        if release.push {
+            log!(
+                "  push '{}' and the new tags to '{}'",
+                branch_name,
+                release.remote
+            );
+        }
+        return Ok(());
+    }
+
+    let mut undo = ReleaseUndo::default();
+    let result = (|| -> Result<()> {
+        let naming = TagNaming::Prefix(opts.tag_prefix);
+        if let Some(created) = ensure_current_version_tag(pubspec_path, &naming, false)? {
+            undo.tags.push(created);
+        }
+
+        for (path, _, content) in &files {
+            let original = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            undo.files.push((path.to_path_buf(), original));
+            fs::write(path, content)
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+        }
+        log!("Bumped version to: {}", plan.new_version);
+
+        let mut index = repo.open_index()?;
+        let mut editor = repo.edit_tree(head.tree_id()?)?;
+        let mut original_ids = Vec::new();
+        for (_, relative, content) in &files {
+            let blob = repo.write_blob(content.as_bytes())?.detach();
+            let entry = index
+                .entry_mut_by_path_and_stage(relative.as_ref(), Stage::Unconflicted)
+                .with_context(|| format!("{} is not in the git index", relative))?;
+            let kind = entry
+                .mode
+                .to_tree_entry_mode()
+                .map_or(gix::object::tree::EntryKind::Blob, |mode| mode.kind());
+            original_ids.push((relative.clone(), entry.id));
+            entry.id = blob;
+            editor.upsert(relative, kind, blob)?;
+        }
+        let tree = editor.write()?.detach();
+        let commit = repo
+            .commit("HEAD", &message, tree, [head.id])
+            .context("Failed to commit the release")?
+            .detach();
+        undo.commit = Some((branch.clone(), head.id, commit));
+        log!(
+            "[bump-version] Committed \"{}\" ({})",
+            message,
+            commit.to_hex_with_len(7)
+        );
+        // The cached tree extension describes the old tree; git rebuilds it on demand.
+        index.remove_tree();
+        index
+            .write(Default::default())
+            .context("Failed to update the git index")?;
+        undo.index = original_ids;
+
+        repo.tag_reference(&tag, commit, PreviousValue::MustNotExist)
+            .with_context(|| format!("Failed to create lightweight tag '{}'", tag))?;
+        undo.tags.push(tag.clone());
+        log!("[bump-version] Created lightweight tag '{}'", tag);
+
+        if release.push {
+            let status = std::process::Command::new("git")
+                .current_dir(&workdir)
+                .args(["push", "--atomic", &release.remote])
+                .arg(branch.as_bstr().to_string())
+                .args(undo.tags.iter().map(|tag| format!("refs/tags/{}", tag)))
+                .status()
+                .context("Failed to run 'git push'")?;
+            if !status.success() {
+                anyhow::bail!("git push to '{}' failed ({})", release.remote, status);
+            }
+            log!(
+                "[bump-version] Pushed '{}' and tags to '{}'",
+                branch_name,
+                release.remote
+            );
+        }
+        Ok(())
+    })();
+
+    if let Err(err) = result {
+        log!("[bump-version] Release failed, rolling back: {:#}", err);
+        if let Err(rollback_err) = undo.rollback(&repo) {
+            return Err(err.context(format!("rollback also failed: {:#}", rollback_err)));
+        }
+        return Err(err);
+    }
+    let commit = undo.commit.as_ref().map(|(_, _, commit)| *commit);
+    let pushed_to = release.push.then_some(release.remote.as_str());
+    record_bump(&plan, &[], undo.tags.clone(), commit, pushed_to)?;
+    log!("[bump-version] Released {}", plan.new_version);
+    report_bumps(opts, vec![plan.summary(None, &[], undo.tags)])
+}
+
+fn main() -> Result<()> {
+    let args = Args::parse();
+    if args.command.bump_options().is_some_and(|opts| opts.json) {
+        LOG_TO_STDERR.store(true, Ordering::Relaxed);
+    }
+
+    match args.command {
+        Command::Major { opts } => do_bump(Bump::Part(VersionPart::Major), &opts),
+        Command::Minor { opts } => do_bump(Bump::Part(VersionPart::Minor), &opts),
+        Command::Patch { opts } => do_bump(Bump::Part(VersionPart::Patch), &opts),
+        Command::Build { opts } => do_bump(Bump::Part(VersionPart::Build), &opts),
+        Command::Auto { opts } => auto_bump(&opts),
+        Command::Pre { channel, bump, opts } => {
+            do_bump(Bump::Pre { channel, part: bump }, &opts)
+        }
+        Command::Promote { opts } => do_bump(Bump::Promote, &opts),
+        Command::Set { version, opts } => do_bump(Bump::Set(version), &opts),
+        Command::Release { part, opts, release } => release_version(part, &opts, &release),
+        Command::Show { pubspec, format } => show_version(Path::new(&pubspec), format),
+        Command::Revert { pubspec, commit } => {
+            revert_bump(Path::new(&pubspec), commit)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value<'a>(content: &'a str, path: &str) -> Option<&'a str> {
+        yaml_value_span(content, path).map(|span| &content[span])
+    }
+
+    #[test]
+    fn line_scalar_span_plain_and_quoted() {
+        let line = "version: 1.2.3+4";
+        assert_eq!(line_scalar_span(line, 8), Some(9..16));
+        let line = "version: \"1.2.3+4\"";
+        assert_eq!(line_scalar_span(line, 8), Some(10..17));
+        let line = "version: '1.2.3'";
+        assert_eq!(line_scalar_span(line, 8), Some(10..15));
+    }
+
+    #[test]
+    fn line_scalar_span_trailing_comment() {
+        let line = "version: 1.2.3+4   # bumped by CI";
+        assert_eq!(&line[line_scalar_span(line, 8).unwrap()], "1.2.3+4");
+        let line = "version: \"1.2.3\" # quoted";
+        assert_eq!(&line[line_scalar_span(line, 8).unwrap()], "1.2.3");
+        assert_eq!(line_scalar_span("version: # none", 8), None);
+        assert_eq!(line_scalar_span("version:", 8), None);
+    }
+
+    #[test]
+    fn line_scalar_span_block_scalar() {
+        assert_eq!(line_scalar_span("version: >-", 8), None);
+        assert_eq!(line_scalar_span("version: |", 8), None);
+    }
+
+    #[test]
+    fn yaml_value_span_top_level() {
+        let content = "name: app\n# version: 0.0.1\nversion: 1.2.3+4 # current\n";
+        assert_eq!(value(content, "version"), Some("1.2.3+4"));
+        let content = "name: app\nflutter:\n  version: 9.9.9\n";
+        assert_eq!(value(content, "version"), None);
+    }
+
+    #[test]
+    fn yaml_value_span_crlf() {
+        let content = "name: app\r\nversion: '1.2.3+4'\r\nflutter:\r\n";
+        let span = yaml_value_span(content, "version").unwrap();
+        assert_eq!(&content[span.clone()], "1.2.3+4");
+        let edited = format!("{}2.0.0{}", &content[..span.start], &content[span.end..]);
+        assert_eq!(edited, "name: app\r\nversion: '2.0.0'\r\nflutter:\r\n");
+    }
+
+    #[test]
+    fn yaml_value_span_nested_path() {
+        let content = "bump_version:\n  # comment\n  build:\n    strategy: date\n  tag: v\n";
+        assert_eq!(value(content, "bump_version.build.strategy"), Some("date"));
+        assert_eq!(value(content, "bump_version.tag"), Some("v"));
+        assert_eq!(value(content, "bump_version.strategy"), None);
+    }
+
+    #[test]
+    fn locate_pubspec_version_rejects_block_scalar() {
+        let path = Path::new("pubspec.yaml");
+        let err = locate_pubspec_version("name: app\nversion: >-\n  1.0.0\n", path).unwrap_err();
+        assert!(
+            err.to_string().contains("not as a plain top-level"),
+            "{}",
+            err
+        );
+        let (span, version) = locate_pubspec_version("version: \"1.0.0+3\"\r\n", path)
+            .unwrap()
+            .unwrap();
+        assert_eq!(span, 10..17);
+        assert_eq!(version, Version::parse("1.0.0+3").unwrap());
+    }
+
+    fn v(version: &str) -> Version {
+        Version::parse(version).unwrap()
+    }
+
+    fn next(current: &str, bump: Bump) -> Result<String> {
+        next_version(&v(current), bump).map(|v| v.to_string())
+    }
+
+    fn pre(channel: PreChannel, part: Option<VersionPart>) -> Bump {
+        Bump::Pre { channel, part }
+    }
+
+    #[test]
+    fn next_version_pre_start() {
+        let beta = pre(PreChannel::Beta, None);
+        assert_eq!(next("1.2.3+5", beta).unwrap(), "1.2.4-beta.1+1");
+        let minor = pre(PreChannel::Alpha, Some(VersionPart::Minor));
+        assert_eq!(next("1.2.3+5", minor).unwrap(), "1.3.0-alpha.1+1");
+        let build = pre(PreChannel::Beta, Some(VersionPart::Build));
+        assert!(next("1.2.3+5", build).is_err());
+    }
+
+    #[test]
+    fn next_version_pre_increment() {
+        let beta = pre(PreChannel::Beta, None);
+        assert_eq!(
+            next("1.3.0-beta.1+1", beta.clone()).unwrap(),
+            "1.3.0-beta.2+2"
+        );
+        assert_eq!(
+            next("1.3.0-alpha.4+7", beta.clone()).unwrap(),
+            "1.3.0-beta.1+8"
+        );
+        assert_eq!(
+            next("1.3.0-beta.2+3", pre(PreChannel::Rc, None)).unwrap(),
+            "1.3.0-rc.1+4"
+        );
+        // Going back to an earlier channel would not be newer.
+        let err = next("1.3.0-rc.1+4", beta).unwrap_err();
+        assert!(err.to_string().contains("--bump"), "{}", err);
+    }
+
+    #[test]
+    fn next_version_promote() {
+        assert_eq!(next("1.3.0-rc.2+9", Bump::Promote).unwrap(), "1.3.0+10");
+        assert_eq!(next("1.3.0-beta.1", Bump::Promote).unwrap(), "1.3.0+1");
+        let err = next("1.3.0+9", Bump::Promote).unwrap_err();
+        assert!(err.to_string().contains("not a prerelease"), "{}", err);
+    }
+
+    fn impact(message: &str) -> Option<Impact> {
+        commit_impact(&Regex::new(CONVENTIONAL_HEADER).unwrap(), message)
+    }
+
+    #[test]
+    fn commit_impact_types() {
+        assert_eq!(impact("feat: add export"), Some(Impact::Minor));
+        assert_eq!(impact("feat(ui): add export"), Some(Impact::Minor));
+        assert_eq!(impact("fix: crash on start"), Some(Impact::Patch));
+        assert_eq!(impact("perf(db): cache queries"), Some(Impact::Patch));
+        assert_eq!(impact("chore: update deps"), None);
+        assert_eq!(impact("Merge branch 'main'"), None);
+        assert_eq!(impact("feat:no space"), None);
+    }
+
+    #[test]
+    fn commit_impact_breaking() {
+        assert_eq!(impact("feat!: drop v1 api"), Some(Impact::Breaking));
+        assert_eq!(impact("refactor(core)!: rename"), Some(Impact::Breaking));
+        let message = "fix: new token format\n\nBREAKING CHANGE: old tokens are rejected\n";
+        assert_eq!(impact(message), Some(Impact::Breaking));
+        let message = "chore: cleanup\n\nBREAKING-CHANGE: config moved\n";
+        assert_eq!(impact(message), Some(Impact::Breaking));
+        // Only a footer counts, not a mention in the body.
+        let message = "fix: typo\n\nNo BREAKING CHANGE: here\n";
+        assert_eq!(impact(message), Some(Impact::Patch));
+    }
+
+    #[test]
+    fn impact_part_zero_major() {
+        assert_eq!(
+            impact_part(Impact::Breaking, &v("1.2.3")),
+            VersionPart::Major
+        );
+        assert_eq!(impact_part(Impact::Minor, &v("1.2.3")), VersionPart::Minor);
+        assert_eq!(impact_part(Impact::Patch, &v("1.2.3")), VersionPart::Patch);
+        assert_eq!(
+            impact_part(Impact::Breaking, &v("0.4.1")),
+            VersionPart::Minor
+        );
+        assert_eq!(impact_part(Impact::Minor, &v("0.4.1")), VersionPart::Patch);
+        assert_eq!(impact_part(Impact::Patch, &v("0.4.1")), VersionPart::Patch);
+    }
+
+    #[test]
+    fn civil_from_days_edges() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(11_017), (2000, 3, 1));
+        // 2100 is not a leap year.
+        assert_eq!(civil_from_days(47_540), (2100, 2, 28));
+        assert_eq!(civil_from_days(47_541), (2100, 3, 1));
+        assert_eq!(civil_from_days(20_088), (2024, 12, 31));
+        assert_eq!(civil_from_days(20_089), (2025, 1, 1));
+    }
+
+    #[test]
+    fn day_build_number_sequence() {
+        assert_eq!(day_build_number(20_088, 5).unwrap(), 24_123_101);
+        assert_eq!(day_build_number(20_088, 24_123_101).unwrap(), 24_123_102);
+        // A new day restarts the sequence and still exceeds the previous day's builds.
+        assert_eq!(day_build_number(20_089, 24_123_102).unwrap(), 25_010_101);
+        assert!(day_build_number(20_089, 25_010_199).is_err());
+    }
+
+    fn with_strategy(current: &str, new: &str, strategy: BuildStrategy) -> Result<String> {
+        let mut new = v(new);
+        apply_build_strategy(&mut new, &v(current), &strategy, Path::new("pubspec.yaml"))?;
+        Ok(new.to_string())
+    }
+
+    #[test]
+    fn apply_build_strategy_monotonic() {
+        let monotonic = BuildStrategy::Monotonic;
+        assert_eq!(
+            with_strategy("1.2.3+41", "1.3.0+1", monotonic.clone()).unwrap(),
+            "1.3.0+42"
+        );
+        assert_eq!(
+            with_strategy("1.2.3", "1.2.4+1", monotonic).unwrap(),
+            "1.2.4+1"
+        );
+        // Reset keeps the bump's build number, even when it goes down.
+        assert_eq!(
+            with_strategy("1.2.3+41", "1.3.0+1", BuildStrategy::Reset).unwrap(),
+            "1.3.0+1"
+        );
+        assert_eq!(
+            with_strategy("1.2.3+41", "1.3.0+1", BuildStrategy::Omit).unwrap(),
+            "1.3.0"
+        );
+    }
+
+    #[test]
+    fn apply_build_strategy_env() {
+        let unset = BuildStrategy::Env("BUMP_VERSION_TEST_UNSET".to_string());
+        let err = with_strategy("1.2.3+4", "1.2.4+1", unset).unwrap_err();
+        assert!(err.to_string().contains("not set"), "{}", err);
+
+        std::env::set_var("BUMP_VERSION_TEST_RUN", " 40 ");
+        let run = || BuildStrategy::Env("BUMP_VERSION_TEST_RUN".to_string());
+        assert_eq!(
+            with_strategy("1.2.3+39", "1.2.4+1", run()).unwrap(),
+            "1.2.4+40"
+        );
+        // A CI number that doesn't move forward is rejected.
+        let err = with_strategy("1.2.3+40", "1.2.4+1", run()).unwrap_err();
+        assert!(err.to_string().contains("does not exceed"), "{}", err);
+    }
+
+    #[test]
+    fn next_version_set() {
+        let set = |version: &str| Bump::Set(v(version));
+        assert_eq!(next("1.2.3+5", set("2.0.0")).unwrap(), "2.0.0+1");
+        assert_eq!(next("1.2.3+5", set("1.2.3+9")).unwrap(), "1.2.3+9");
+        // A lower version is allowed, with a warning.
+        assert_eq!(next("1.2.3+5", set("1.0.0+7")).unwrap(), "1.0.0+7");
+        assert!(next("1.2.3+5", set("1.2.3")).is_err());
+        assert!(next("1.2.3+5", set("1.2.3+5")).is_err());
+    }
+
+    #[test]
+    fn parse_version_rejects_invalid() {
+        assert!(parse_version(" 1.2.3+4 ").is_ok());
+        assert!(parse_version("1.2").is_err());
+        assert!(parse_version("v1.2.3").is_err());
+        assert!(parse_version("1.2.3+build.5").is_err());
+    }
+
+    fn span_text(content: &str, span: Option<Range<usize>>) -> Option<&str> {
+        span.map(|span| &content[span])
+    }
+
+    #[test]
+    fn json_value_span_pointer() {
+        let content = r#"{
+    "name": "app",
+    "meta": {"version": "0.0.1", "tags": ["version"]},
+    "version": "1.2.3+4",
+    "a/b": {"~x": "9.9.9"}
+}"#;
+        assert_eq!(
+            span_text(content, json_value_span(content, "/version")),
+            Some("1.2.3+4")
+        );
+        assert_eq!(
+            span_text(content, json_value_span(content, "/meta/version")),
+            Some("0.0.1")
+        );
+        assert_eq!(
+            span_text(content, json_value_span(content, "/a~1b/~0x")),
+            Some("9.9.9")
+        );
+        assert_eq!(json_value_span(content, "/missing"), None);
+        // Only string values can be rewritten in place.
+        assert_eq!(json_value_span(r#"{"version": 3}"#, "/version"), None);
+    }
+
+    #[test]
+    fn toml_value_span_tables_and_dotted_keys() {
+        let content = "# version = \"0.0.0\"\n[package]\nversion = \"1.2.3\" # current\n\n[tool.app]\nversion = '2.0.0'\n";
+        assert_eq!(
+            span_text(content, toml_value_span(content, "package.version")),
+            Some("1.2.3")
+        );
+        assert_eq!(
+            span_text(content, toml_value_span(content, "tool.app.version")),
+            Some("2.0.0")
+        );
+        let content = "app.version = \"3.1.0\"\n";
+        assert_eq!(
+            span_text(content, toml_value_span(content, "app.version")),
+            Some("3.1.0")
+        );
+        assert_eq!(toml_value_span(content, "version"), None);
+    }
+
+    #[test]
+    fn pkl_value_span_nested_property() {
+        let content = "// version = \"0.0.0\"\npubspec {\n  name = \"app\"\n  version: String = \"1.2.3+4\"\n}\nversion = \"9.9.9\"\n";
+        assert_eq!(
+            span_text(content, pkl_value_span(content, "pubspec.version")),
+            Some("1.2.3+4")
+        );
+        assert_eq!(
+            span_text(content, pkl_value_span(content, "version")),
+            Some("9.9.9")
+        );
+        assert_eq!(pkl_value_span(content, "pubspec.build"), None);
+    }
+
+    #[test]
+    fn version_value_render() {
+        let version = v("1.3.0-beta.2+7");
+        assert_eq!(VersionValue::Full.render(&version), "1.3.0-beta.2+7");
+        assert_eq!(VersionValue::Name.render(&version), "1.3.0-beta.2");
+    }
+
+    #[test]
+    fn update_dependency_constraints() {
+        let mut content = "name: app\ndependencies:\n  core: ^1.0.0 # caret\n  other: any\ndev_dependencies:\n  core:\n    path: ../core\n    version: \"1.0.0\"\ndependency_overrides:\n  core: \">=1.0.0 <2.0.0\"\n".to_string();
+        let edits = update_dependency(
+            &mut content,
+            Path::new("pubspec.yaml"),
+            "core",
+            &v("2.1.0+5"),
+        );
+        assert_eq!(
+            content,
+            "name: app\ndependencies:\n  core: ^2.1.0 # caret\n  other: any\ndev_dependencies:\n  core:\n    path: ../core\n    version: \"2.1.0\"\ndependency_overrides:\n  core: \">=1.0.0 <2.0.0\"\n"
+        );
+        let edits: Vec<_> = edits
+            .iter()
+            .map(|e| (e.key.as_str(), e.old.as_str(), e.new.as_str()))
+            .collect();
+        assert_eq!(
+            edits,
+            [
+                ("dependencies.core", "^1.0.0", "^2.1.0"),
+                ("dev_dependencies.core.version", "1.0.0", "2.1.0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn update_dependency_unchanged() {
+        let original = "dependencies:\n  core: ^2.1.0\n  ui: any\n";
+        let mut content = original.to_string();
+        let edits = update_dependency(&mut content, Path::new("pubspec.yaml"), "core", &v("2.1.0"));
+        assert!(edits.is_empty());
+        assert!(
+            update_dependency(&mut content, Path::new("pubspec.yaml"), "ui", &v("1.0.0"))
+                .is_empty()
+        );
+        assert_eq!(content, original);
+    }
+
+    fn entry(pubspec: &str, old: &str, new: &str, commit: Option<&str>) -> JournalEntry {
+        JournalEntry {
+            pubspec: pubspec.to_string(),
+            files: Vec::new(),
+            old_version: old.to_string(),
+            new_version: new.to_string(),
+            tags: Vec::new(),
+            commit: commit.map(str::to_string),
+            pushed_to: None,
+            constraints: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn stacked_bumps_chain() {
+        let mut journal = vec![
+            entry("pubspec.yaml", "1.0.0", "1.0.1", None),
+            entry("packages/core/pubspec.yaml", "0.1.0", "0.2.0", None),
+            entry("pubspec.yaml", "1.0.1", "1.1.0-beta.1", None),
+        ];
+        // The newest entry (1.1.0-beta.1 → 1.1.0-beta.2) was already removed by the caller.
+        let older = stacked_bumps(&mut journal, "pubspec.yaml", "1.1.0-beta.1", "1.0.0").unwrap();
+        let versions: Vec<_> = older.iter().map(|e| e.new_version.as_str()).collect();
+        assert_eq!(versions, ["1.1.0-beta.1", "1.0.1"]);
+        assert_eq!(journal.len(), 1);
+        assert_eq!(journal[0].pubspec, "packages/core/pubspec.yaml");
+    }
+
+    #[test]
+    fn stacked_bumps_broken_chain() {
+        let mut journal = vec![
+            entry("pubspec.yaml", "1.0.0", "1.0.1", Some("abc123")),
+            entry("pubspec.yaml", "1.0.1", "1.0.2", None),
+        ];
+        // A committed bump ends the uncommitted chain before HEAD's version is reached.
+        assert!(stacked_bumps(&mut journal, "pubspec.yaml", "1.0.2", "1.0.0").is_none());
+        // A gap in the versions isn't a chain either.
+        assert!(stacked_bumps(&mut journal, "pubspec.yaml", "1.0.3", "1.0.1").is_none());
+        assert_eq!(journal.len(), 2);
+    }
+}
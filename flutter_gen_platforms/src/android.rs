@@ -1,9 +1,15 @@
-use anyhow::{Context, Result};
-use std::collections::HashMap;
+use anyhow::{bail, Context, Result};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
 
-use crate::config::AndroidConfig;
+use crate::config::{AndroidConfig, NetworkSecurityConfig};
+use crate::manifest::{edit_start_tag, escape_attr, set_attribute, MANIFEST_PATH};
+use crate::proguard;
+use crate::report::{self, FileAction};
+use crate::utils::platforms_root;
+use crate::version::set_plist_string;
 
 /// Files to skip when copying from platforms/android/ to android/.
 const SKIP_FILES: &[&str] = &["keystore.jks"];
@@ -11,6 +17,9 @@ const SKIP_FILES: &[&str] = &["keystore.jks"];
 /// File extensions that support `{{var}}` template substitution.
 const TEMPLATE_EXTENSIONS: &[&str] = &["kts", "xml", "properties"];
 
+/// Locale key in `AndroidConfig::strings` that maps to `res/values/`.
+pub const DEFAULT_LOCALE: &str = "default";
+
 /// Recursively copy files from `src` to `dst`, applying `{{var}}` template
 /// substitution on supported file types. Files listed in `SKIP_FILES` are
 /// not copied.
@@ -33,6 +42,7 @@ fn copy_with_templates(src: &Path, dst: &Path, vars: &HashMap<String, String>) -
         // Skip non-copyable files
         let name = file_name.to_string_lossy();
         if SKIP_FILES.iter().any(|s| *s == name.as_ref()) {
+            report::skipped(&src_path, "never copied from the overlay");
             continue;
         }
 
@@ -40,8 +50,7 @@ fn copy_with_templates(src: &Path, dst: &Path, vars: &HashMap<String, String>) -
         let is_template = src_path
             .extension()
             .and_then(|ext| ext.to_str())
-            .map(|ext| TEMPLATE_EXTENSIONS.iter().any(|t| *t == ext))
-            .unwrap_or(false);
+            .is_some_and(|ext| TEMPLATE_EXTENSIONS.contains(&ext));
 
         if is_template && !vars.is_empty() {
             let content = fs::read_to_string(&src_path)
@@ -52,6 +61,7 @@ fn copy_with_templates(src: &Path, dst: &Path, vars: &HashMap<String, String>) -
             }
             fs::write(&dst_path, rendered)
                 .with_context(|| format!("Failed to write: {}", dst_path.display()))?;
+            report::file(FileAction::Templated, &dst_path);
         } else {
             if let Some(parent) = dst_path.parent() {
                 fs::create_dir_all(parent)?;
@@ -63,6 +73,7 @@ fn copy_with_templates(src: &Path, dst: &Path, vars: &HashMap<String, String>) -
                     dst_path.display()
                 )
             })?;
+            report::file(FileAction::Copied, &dst_path);
         }
     }
 
@@ -80,13 +91,18 @@ fn apply_template(content: &str, vars: &HashMap<String, String>) -> String {
 }
 
 pub fn apply_gradle_wrapper_properties(path: &Path, distribution_url: &str) -> Result<()> {
-    let mut props = read_properties(path)?;
-    props.insert("distributionUrl".to_string(), distribution_url.to_string());
-    write_properties(path, &props)?;
+    let content = if path.exists() {
+        fs::read_to_string(path).with_context(|| format!("Failed to read: {}", path.display()))?
+    } else {
+        String::new()
+    };
+    let updated = set_properties_value(&content, "distributionUrl", distribution_url);
+    fs::write(path, updated).with_context(|| format!("Failed to write: {}", path.display()))?;
+    report::file(FileAction::Patched, path);
     Ok(())
 }
 
-fn read_properties(path: &Path) -> Result<HashMap<String, String>> {
+pub fn read_properties(path: &Path) -> Result<HashMap<String, String>> {
     if !path.exists() {
         return Ok(HashMap::new());
     }
@@ -97,26 +113,147 @@ fn read_properties(path: &Path) -> Result<HashMap<String, String>> {
     Ok(props)
 }
 
-fn write_properties(path: &Path, props: &HashMap<String, String>) -> Result<()> {
-    let file = fs::File::create(path)
-        .with_context(|| format!("Failed to write file: {}", path.display()))?;
-    java_properties::write(std::io::BufWriter::new(file), props)
-        .with_context(|| format!("Failed to write properties: {}", path.display()))?;
-    Ok(())
+/// Set `key` in the text of a .properties file. Only the value of the key's
+/// line changes (or a line is appended), so comments, blank lines and key
+/// order are kept.
+pub fn set_properties_value(content: &str, key: &str, value: &str) -> String {
+    let ending = if content.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let value = escape_property(value, false);
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let mut out = String::with_capacity(content.len() + value.len());
+    let mut found = false;
+    let mut i = 0;
+    while i < lines.len() {
+        // A logical line continues while it ends in an odd number of backslashes.
+        let start = i;
+        while i + 1 < lines.len() && continues(lines[i]) {
+            i += 1;
+        }
+        i += 1;
+        let first = lines[start];
+        match property_value_start(first) {
+            Some((line_key, value_start, separated)) if line_key == key => {
+                let last = lines[i - 1];
+                let line_ending = &last[last.trim_end_matches(['\r', '\n']).len()..];
+                out.push_str(&first[..value_start]);
+                // A bare `key` line has no separator yet.
+                if !separated {
+                    out.push('=');
+                }
+                out.push_str(&value);
+                out.push_str(line_ending);
+                found = true;
+            }
+            _ => out.push_str(&lines[start..i].concat()),
+        }
+    }
+    if !found {
+        if !out.is_empty() && !out.ends_with('\n') {
+            out.push_str(ending);
+        }
+        out.push_str(&format!("{}={value}{ending}", escape_property(key, true)));
+    }
+    out
+}
+
+fn continues(line: &str) -> bool {
+    let line = line.trim_end_matches(['\r', '\n']);
+    (line.len() - line.trim_end_matches('\\').len()) % 2 == 1
+}
+
+/// The unescaped key of a property line, the offset where its value starts
+/// and whether a separator follows the key; `None` for blank and comment
+/// lines.
+fn property_value_start(line: &str) -> Option<(String, usize, bool)> {
+    let trimmed = line.trim_start_matches([' ', '\t', '\x0c']);
+    if trimmed.trim_end().is_empty() || trimmed.starts_with(['#', '!']) {
+        return None;
+    }
+    let offset = line.len() - trimmed.len();
+    let mut key = String::new();
+    let mut chars = trimmed.char_indices();
+    let mut key_end = trimmed.len();
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '\\' => {
+                if let Some((_, escaped)) = chars.next() {
+                    key.push(escaped);
+                }
+            }
+            '=' | ':' | ' ' | '\t' | '\x0c' | '\r' | '\n' => {
+                key_end = pos;
+                break;
+            }
+            _ => key.push(c),
+        }
+    }
+    // Whitespace, then at most one `=` or `:`, then whitespace.
+    let rest = &trimmed[key_end..];
+    let mut value = rest.trim_start_matches([' ', '\t', '\x0c']);
+    if let Some(after) = value.strip_prefix(['=', ':']) {
+        value = after.trim_start_matches([' ', '\t', '\x0c']);
+    }
+    let value_start = offset + trimmed.len() - value.len();
+    // Keep the line ending out of the prefix.
+    let body_end = line.trim_end_matches(['\r', '\n']).len();
+    Some((key, value_start.min(body_end), offset + key_end < body_end))
+}
+
+fn escape_property(text: &str, is_key: bool) -> String {
+    let mut out = String::with_capacity(text.len());
+    for (i, c) in text.chars().enumerate() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            ' ' if is_key || i == 0 => out.push_str("\\ "),
+            '=' | ':' | '#' | '!' if is_key => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Paths, relative to android/, that the overlay copy writes.
+pub fn overlay_files(project_dir: &Path, platforms_dir: Option<&str>) -> Result<Vec<PathBuf>> {
+    let src_dir = project_dir
+        .join(platforms_root(platforms_dir))
+        .join("android");
+    if !src_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in WalkDir::new(&src_dir) {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy();
+        if !entry.file_type().is_file() || SKIP_FILES.iter().any(|s| *s == name.as_ref()) {
+            continue;
+        }
+        files.push(entry.path().strip_prefix(&src_dir)?.to_path_buf());
+    }
+    Ok(files)
 }
 
+/// Copy the overlay from `project_dir` into `app_dir`/android and apply the
+/// generated resources and build settings.
 pub fn process_android_platform(
     project_dir: &Path,
+    app_dir: &Path,
     config: &AndroidConfig,
     platforms_dir: Option<&str>,
     template_vars: &HashMap<String, String>,
 ) -> Result<()> {
-    let android_dir = project_dir.join("android");
+    let android_dir = app_dir.join("android");
 
-    let platforms_root = platforms_dir
-        .map(|v| v.trim())
-        .filter(|v| !v.is_empty())
-        .unwrap_or("platforms");
+    let platforms_root = platforms_root(platforms_dir);
     let src_dir = project_dir.join(platforms_root).join("android");
 
     if !src_dir.exists() {
@@ -137,6 +274,525 @@ pub fn process_android_platform(
         )?;
     }
 
-    println!("Android directory generated at: {}", android_dir.display());
+    if !config.strings.is_empty() {
+        write_string_resources(&android_dir, &config.strings)?;
+    }
+
+    if let Some(network_security) = &config.network_security {
+        write_network_security_config(&android_dir, network_security)?;
+    }
+
+    if let Some(proguard) = &config.proguard {
+        proguard::apply_proguard_config(&android_dir, proguard)?;
+    }
+
+    log!("Android directory generated at: {}", android_dir.display());
+    Ok(())
+}
+
+/// Write `res/values[-<qualifier>]/strings.xml` for every configured locale and
+/// point the manifest label at `@string/app_name` when one is provided.
+fn write_string_resources(
+    android_dir: &Path,
+    strings: &BTreeMap<String, BTreeMap<String, String>>,
+) -> Result<()> {
+    let Some(defaults) = strings.get(DEFAULT_LOCALE) else {
+        bail!("android.strings must define a `{DEFAULT_LOCALE}` locale");
+    };
+
+    let res_dir = android_dir.join("app/src/main/res");
+    for (locale, values) in strings {
+        for name in values.keys() {
+            if !is_valid_resource_name(name) {
+                bail!("Invalid string resource name in android.strings.{locale}: {name}");
+            }
+            if !defaults.contains_key(name) {
+                bail!("android.strings.{locale}.{name} has no `{DEFAULT_LOCALE}` value");
+            }
+        }
+
+        let values_dir = if locale == DEFAULT_LOCALE {
+            "values".to_string()
+        } else {
+            format!("values-{}", android_locale_qualifier(locale)?)
+        };
+        let path = res_dir.join(values_dir).join("strings.xml");
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&path, render_strings_xml(values))
+            .with_context(|| format!("Failed to write: {}", path.display()))?;
+        report::file(FileAction::Created, &path);
+    }
+
+    if defaults.contains_key("app_name") {
+        set_application_attribute(android_dir, "android:label", "@string/app_name")?;
+    }
+
+    log!(
+        "✓ Android string resources written for {} locale(s)",
+        strings.len()
+    );
+    Ok(())
+}
+
+/// Set `CFBundleDisplayName` in Info.plist content, adding the key at the end
+/// of the top-level dict when the template doesn't have it.
+fn set_display_name(content: &str, name: &str) -> Result<String> {
+    if content.contains("<key>CFBundleDisplayName</key>") {
+        return set_plist_string(content, "CFBundleDisplayName", name);
+    }
+    let end = content.rfind("</dict>").context("No <dict> found")?;
+    Ok(format!(
+        "{}\t<key>CFBundleDisplayName</key>\n\t<string>{}</string>\n{}",
+        &content[..end],
+        escape_attr(name),
+        &content[end..]
+    ))
+}
+
+/// Give iOS the same localized app names as Android: `CFBundleDisplayName`
+/// in Info.plist for the default locale and `<locale>.lproj/InfoPlist.strings`
+/// for the others. Skipped when there is no ios/Runner.
+pub fn write_ios_display_names(
+    app_dir: &Path,
+    strings: &BTreeMap<String, BTreeMap<String, String>>,
+) -> Result<()> {
+    let runner_dir = app_dir.join("ios").join("Runner");
+    if !runner_dir.is_dir() {
+        return Ok(());
+    }
+    let mut count = 0;
+    let mut lproj_written = false;
+    for (locale, values) in strings {
+        let Some(name) = values.get("app_name") else {
+            continue;
+        };
+        if locale == DEFAULT_LOCALE {
+            let path = runner_dir.join("Info.plist");
+            if !path.exists() {
+                continue;
+            }
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read: {}", path.display()))?;
+            let updated = set_display_name(&content, name)
+                .with_context(|| format!("Failed to update {}", path.display()))?;
+            fs::write(&path, updated)
+                .with_context(|| format!("Failed to write: {}", path.display()))?;
+            report::file(FileAction::Patched, &path);
+        } else {
+            let path = runner_dir
+                .join(format!("{}.lproj", ios_locale(locale)))
+                .join("InfoPlist.strings");
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let escaped = name
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            fs::write(&path, format!("\"CFBundleDisplayName\" = \"{escaped}\";\n"))
+                .with_context(|| format!("Failed to write: {}", path.display()))?;
+            report::file(FileAction::Created, &path);
+            lproj_written = true;
+        }
+        count += 1;
+    }
+    if count == 0 {
+        return Ok(());
+    }
+
+    // Xcode only bundles .lproj files that belong to the Runner target.
+    let pbxproj = app_dir.join("ios/Runner.xcodeproj/project.pbxproj");
+    if lproj_written
+        && fs::read_to_string(&pbxproj).is_ok_and(|content| !content.contains("InfoPlist.strings"))
+    {
+        report::warn(format!(
+            "Add ios/Runner/*.lproj/InfoPlist.strings to the Runner target in Xcode ({} doesn't reference them)",
+            pbxproj.display()
+        ));
+    }
+    log!("✓ iOS display names written for {count} locale(s)");
+    Ok(())
+}
+
+/// Convert a locale in config or Android form (`pt-BR`, `pt-rBR`, `b+zh+Hans`)
+/// into an iOS `.lproj` name (`pt-BR`, `zh-Hans`).
+fn ios_locale(locale: &str) -> String {
+    if let Some(tags) = locale.strip_prefix("b+") {
+        return tags.replace('+', "-");
+    }
+    locale.replacen("-r", "-", 1).replace('_', "-")
+}
+
+/// Convert a locale such as `fr`, `pt-BR` or `zh-Hans` into an Android
+/// resource qualifier (`fr`, `pt-rBR`, `b+zh+Hans`). Qualifiers already in
+/// Android form are passed through.
+fn android_locale_qualifier(locale: &str) -> Result<String> {
+    if locale.starts_with("b+") || locale.contains("-r") {
+        return Ok(locale.to_string());
+    }
+    let parts: Vec<&str> = locale.split(['-', '_']).collect();
+    if parts
+        .iter()
+        .any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_alphanumeric()))
+    {
+        bail!("Invalid locale in android.strings: {locale}");
+    }
+    match parts.as_slice() {
+        [lang] => Ok(lang.to_ascii_lowercase()),
+        [lang, region]
+            if (region.len() == 2 && region.chars().all(|c| c.is_ascii_alphabetic()))
+                || (region.len() == 3 && region.chars().all(|c| c.is_ascii_digit())) =>
+        {
+            Ok(format!(
+                "{}-r{}",
+                lang.to_ascii_lowercase(),
+                region.to_ascii_uppercase()
+            ))
+        }
+        _ => Ok(format!("b+{}", parts.join("+"))),
+    }
+}
+
+fn is_valid_resource_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+}
+
+fn render_strings_xml(values: &BTreeMap<String, String>) -> String {
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<resources>\n");
+    for (name, value) in values {
+        out.push_str(&format!(
+            "    <string name=\"{}\">{}</string>\n",
+            name,
+            escape_string_resource(value)
+        ));
+    }
+    out.push_str("</resources>\n");
+    out
+}
+
+/// Escape a value for an Android `<string>` resource (XML entities plus the
+/// aapt-specific quote and leading `@`/`?` escapes).
+fn escape_string_resource(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '"' => out.push_str("\\\""),
+            '\n' => out.push_str("\\n"),
+            '@' | '?' if i == 0 => {
+                out.push('\\');
+                out.push(c);
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Set an attribute on the `<application>` tag of the main manifest.
+fn set_application_attribute(android_dir: &Path, name: &str, value: &str) -> Result<()> {
+    let manifest_path = android_dir.join(MANIFEST_PATH);
+    let content = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read: {}", manifest_path.display()))?;
+    let updated = edit_start_tag(&content, "application", |tag| {
+        set_attribute(tag, name, value)
+    })
+    .with_context(|| format!("No <application> tag in: {}", manifest_path.display()))?;
+    fs::write(&manifest_path, updated)
+        .with_context(|| format!("Failed to write: {}", manifest_path.display()))?;
+    report::file(FileAction::Patched, &manifest_path);
+    Ok(())
+}
+
+/// Write `res/xml/network_security_config.xml` and reference it from the
+/// manifest.
+fn write_network_security_config(android_dir: &Path, config: &NetworkSecurityConfig) -> Result<()> {
+    let mut out =
+        String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<network-security-config>\n");
+
+    if !config.cleartext_domains.is_empty() {
+        out.push_str("    <domain-config cleartextTrafficPermitted=\"true\">\n");
+        for domain in &config.cleartext_domains {
+            out.push_str(&format!(
+                "        <domain includeSubdomains=\"true\">{}</domain>\n",
+                escape_attr(domain)
+            ));
+        }
+        out.push_str("    </domain-config>\n");
+    }
+
+    for pin in &config.pins {
+        if pin.sha256.is_empty() {
+            bail!(
+                "android.network_security.pins for {} needs at least one sha256 digest",
+                pin.domain
+            );
+        }
+        out.push_str("    <domain-config>\n");
+        out.push_str(&format!(
+            "        <domain includeSubdomains=\"{}\">{}</domain>\n",
+            pin.include_subdomains,
+            escape_attr(&pin.domain)
+        ));
+        match &pin.expiration {
+            Some(expiration) => {
+                check_pin_expiration(&pin.domain, expiration)?;
+                out.push_str(&format!(
+                    "        <pin-set expiration=\"{}\">\n",
+                    escape_attr(expiration)
+                ));
+            }
+            None => out.push_str("        <pin-set>\n"),
+        }
+        for digest in &pin.sha256 {
+            out.push_str(&format!(
+                "            <pin digest=\"SHA-256\">{}</pin>\n",
+                escape_attr(digest)
+            ));
+        }
+        out.push_str("        </pin-set>\n    </domain-config>\n");
+    }
+
+    if !config.debug_trust_anchors.is_empty() {
+        out.push_str("    <debug-overrides>\n        <trust-anchors>\n");
+        for anchor in &config.debug_trust_anchors {
+            if !(anchor == "user" || anchor == "system" || anchor.starts_with("@raw/")) {
+                bail!(
+                    "Invalid debug trust anchor '{anchor}' (expected user, system or @raw/<name>)"
+                );
+            }
+            out.push_str(&format!(
+                "            <certificates src=\"{}\" />\n",
+                escape_attr(anchor)
+            ));
+        }
+        out.push_str("        </trust-anchors>\n    </debug-overrides>\n");
+    }
+
+    out.push_str("</network-security-config>\n");
+
+    let path = android_dir.join("app/src/main/res/xml/network_security_config.xml");
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, out).with_context(|| format!("Failed to write: {}", path.display()))?;
+    report::file(FileAction::Created, &path);
+    set_application_attribute(
+        android_dir,
+        "android:networkSecurityConfig",
+        "@xml/network_security_config",
+    )?;
+
+    log!("✓ Android network security config generated");
+    Ok(())
+}
+
+/// Validate a `YYYY-MM-DD` pin expiry and warn when it has already passed,
+/// since Android silently stops pinning after that date.
+fn check_pin_expiration(domain: &str, expiration: &str) -> Result<()> {
+    let parts: Vec<&str> = expiration.split('-').collect();
+    let parsed = match parts.as_slice() {
+        [y, m, d] if y.len() == 4 && m.len() == 2 && d.len() == 2 => {
+            match (y.parse::<i64>(), m.parse::<u32>(), d.parse::<u32>()) {
+                (Ok(y), Ok(m @ 1..=12), Ok(d @ 1..=31)) => Some((y, m, d)),
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+    let Some((year, month, day)) = parsed else {
+        bail!("Invalid pin expiration for {domain}: {expiration} (expected YYYY-MM-DD)");
+    };
+
+    let today_days = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| (d.as_secs() / 86_400) as i64)
+        .unwrap_or_default();
+    if days_from_civil(year, month, day) < today_days {
+        report::warn(format!(
+            "Certificate pins for {domain} expired on {expiration} and are no longer enforced"
+        ));
+    }
     Ok(())
 }
+
+/// Days since 1970-01-01 for a proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plist_value(content: &str) -> String {
+        let start = content.find("<string>").unwrap() + "<string>".len();
+        let end = content[start..].find("</string>").unwrap() + start;
+        content[start..end]
+            .replace("&lt;", "<")
+            .replace("&gt;", ">")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&")
+    }
+
+    #[test]
+    fn set_display_name_replaces_existing_key() {
+        let content = "<dict>\n\t<key>CFBundleDisplayName</key>\n\t<string>App</string>\n</dict>\n";
+        let updated = set_display_name(content, "A & <B>").unwrap();
+        assert!(updated.contains("<string>A &amp; &lt;B></string>"));
+        assert_eq!(plist_value(&updated), "A & <B>");
+    }
+
+    #[test]
+    fn set_display_name_inserts_missing_key() {
+        let content = "<dict>\n\t<key>CFBundleName</key>\n</dict>\n";
+        let updated = set_display_name(content, "A & <B>").unwrap();
+        assert!(updated.contains("<key>CFBundleDisplayName</key>"));
+        assert!(updated.ends_with("</dict>\n"));
+        assert_eq!(plist_value(&updated), "A & <B>");
+    }
+
+    #[test]
+    fn android_locale_qualifier_forms() {
+        assert_eq!(android_locale_qualifier("fr").unwrap(), "fr");
+        assert_eq!(android_locale_qualifier("pt-BR").unwrap(), "pt-rBR");
+        assert_eq!(android_locale_qualifier("es_419").unwrap(), "es-r419");
+        assert_eq!(android_locale_qualifier("zh-Hans").unwrap(), "b+zh+Hans");
+        assert_eq!(
+            android_locale_qualifier("sr-Latn-RS").unwrap(),
+            "b+sr+Latn+RS"
+        );
+        // Already in Android form.
+        assert_eq!(android_locale_qualifier("pt-rBR").unwrap(), "pt-rBR");
+        assert_eq!(android_locale_qualifier("b+zh+Hans").unwrap(), "b+zh+Hans");
+        assert!(android_locale_qualifier("pt--BR").is_err());
+        assert!(android_locale_qualifier("en/US").is_err());
+    }
+
+    #[test]
+    fn ios_locale_forms() {
+        assert_eq!(ios_locale("fr"), "fr");
+        assert_eq!(ios_locale("pt-BR"), "pt-BR");
+        assert_eq!(ios_locale("pt_BR"), "pt-BR");
+        assert_eq!(ios_locale("pt-rBR"), "pt-BR");
+        assert_eq!(ios_locale("b+zh+Hans"), "zh-Hans");
+    }
+
+    #[test]
+    fn escape_string_resource_specials() {
+        assert_eq!(
+            escape_string_resource("Tom & Jerry <3"),
+            "Tom &amp; Jerry &lt;3"
+        );
+        assert_eq!(escape_string_resource("It's \"on\""), "It\\'s \\\"on\\\"");
+        assert_eq!(escape_string_resource("a\\b\nc"), "a\\\\b\\nc");
+        assert_eq!(escape_string_resource("@home"), "\\@home");
+        assert_eq!(escape_string_resource("?help"), "\\?help");
+        assert_eq!(escape_string_resource("mail@host?"), "mail@host?");
+    }
+
+    #[test]
+    fn days_from_civil_edges() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(2000, 2, 29), 11_016);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(2100, 3, 1), 47_541);
+        assert_eq!(days_from_civil(2025, 1, 1), 20_089);
+    }
+
+    #[test]
+    fn check_pin_expiration_format() {
+        assert!(check_pin_expiration("example.com", "2999-12-31").is_ok());
+        // Past dates are only warned about.
+        assert!(check_pin_expiration("example.com", "2001-01-01").is_ok());
+        for invalid in [
+            "2999-1-31",
+            "2999/12/31",
+            "2999-13-01",
+            "2999-12-32",
+            "31-12-2999",
+            "",
+        ] {
+            let err = check_pin_expiration("example.com", invalid).unwrap_err();
+            assert!(err.to_string().contains("YYYY-MM-DD"), "{err}");
+        }
+    }
+
+    #[test]
+    fn set_properties_value_keeps_layout() {
+        let content = "# Gradle\r\norg.gradle.jvmargs=-Xmx4G\r\n\r\nandroid.useAndroidX = true\r\n";
+        assert_eq!(
+            set_properties_value(content, "android.useAndroidX", "false"),
+            "# Gradle\r\norg.gradle.jvmargs=-Xmx4G\r\n\r\nandroid.useAndroidX = false\r\n"
+        );
+        assert_eq!(
+            set_properties_value(content, "flutter.minSdk", "23"),
+            format!("{content}flutter.minSdk=23\r\n")
+        );
+        assert_eq!(set_properties_value("a=1", "b", "2"), "a=1\nb=2\n");
+    }
+
+    #[test]
+    fn set_properties_value_continuations_and_escapes() {
+        let content = "key = one \\\n    two\nnext=3\n";
+        assert_eq!(
+            set_properties_value(content, "key", "x"),
+            "key = x\nnext=3\n"
+        );
+        // `\\` at the end is an escaped backslash, not a continuation.
+        let content = "dir=C:\\\\\nkey=1\n";
+        assert_eq!(
+            set_properties_value(content, "key", "2"),
+            "dir=C:\\\\\nkey=2\n"
+        );
+        assert_eq!(set_properties_value("bare\n", "bare", "v"), "bare=v\n");
+        assert_eq!(
+            set_properties_value("", "my key", " lead\\"),
+            "my\\ key=\\ lead\\\\\n"
+        );
+    }
+
+    #[test]
+    fn property_value_start_separators() {
+        assert_eq!(
+            property_value_start("a=1"),
+            Some(("a".to_string(), 2, true))
+        );
+        assert_eq!(
+            property_value_start("  a : 1\n"),
+            Some(("a".to_string(), 6, true))
+        );
+        assert_eq!(
+            property_value_start("a 1"),
+            Some(("a".to_string(), 2, true))
+        );
+        assert_eq!(
+            property_value_start("a\\=b=c"),
+            Some(("a=b".to_string(), 5, true))
+        );
+        assert_eq!(
+            property_value_start("bare\r\n"),
+            Some(("bare".to_string(), 4, false))
+        );
+        assert_eq!(property_value_start("# comment=1"), None);
+        assert_eq!(property_value_start("! comment"), None);
+        assert_eq!(property_value_start("   \n"), None);
+    }
+}
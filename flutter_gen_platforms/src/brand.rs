@@ -0,0 +1,260 @@
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::config::BrandConfig;
+use crate::report::{self, FileAction};
+use crate::utils::remove_dir_all_with_retry;
+use crate::version::update_web_manifest;
+
+/// Top-level entries never copied into a brand output directory.
+const SKIP_DIRS: &[&str] = &[".git", ".dart_tool", "build"];
+
+/// Where the project's own `assets/` wait while a brand's assets are in
+/// place, relative to the project dir. A copy on disk survives a failed run.
+const ASSETS_BACKUP_DIR: &str = ".dart_tool/flutter_gen_platforms/assets";
+
+/// The project's `assets/`, set aside while `--brand` generates in place.
+/// Put back by `restore`, or when dropped on an error path.
+pub struct AssetsBackup {
+    assets_dir: PathBuf,
+    backup_dir: PathBuf,
+    restored: bool,
+}
+
+impl AssetsBackup {
+    /// Copy `assets/` aside, first putting back a backup an interrupted run
+    /// left behind.
+    fn create(project_dir: &Path) -> Result<Self> {
+        let mut backup = AssetsBackup {
+            assets_dir: project_dir.join("assets"),
+            backup_dir: project_dir.join(ASSETS_BACKUP_DIR),
+            // Nothing to put back until the copy is complete.
+            restored: true,
+        };
+        if backup.backup_dir.exists() {
+            report::warn(format!(
+                "Restoring assets/ left by an interrupted brand run from {}",
+                backup.backup_dir.display()
+            ));
+            backup.restore_files()?;
+        }
+        fs::create_dir_all(&backup.backup_dir)
+            .with_context(|| format!("Failed to create dir: {}", backup.backup_dir.display()))?;
+        if backup.assets_dir.is_dir() {
+            let saved = backup.backup_dir.join("assets");
+            if let Err(err) = copy_tree(&backup.assets_dir, &saved, |_| {}) {
+                // A partial copy must never be restored over the real assets.
+                let _ = fs::remove_dir_all(&backup.backup_dir);
+                return Err(err);
+            }
+        }
+        backup.restored = false;
+        Ok(backup)
+    }
+
+    /// Replace `assets/` with the project's own again.
+    pub fn restore(mut self) -> Result<()> {
+        self.restore_files()?;
+        log!("✓ Project assets restored");
+        Ok(())
+    }
+
+    fn restore_files(&mut self) -> Result<()> {
+        if self.assets_dir.exists() {
+            remove_dir_all_with_retry(&self.assets_dir)?;
+        }
+        let saved = self.backup_dir.join("assets");
+        if saved.exists() {
+            fs::rename(&saved, &self.assets_dir).with_context(|| {
+                format!(
+                    "Failed to move {} -> {}",
+                    saved.display(),
+                    self.assets_dir.display()
+                )
+            })?;
+        }
+        remove_dir_all_with_retry(&self.backup_dir)?;
+        if let Some(parent) = self.backup_dir.parent() {
+            let _ = fs::remove_dir(parent);
+        }
+        self.restored = true;
+        Ok(())
+    }
+}
+
+impl Drop for AssetsBackup {
+    fn drop(&mut self) {
+        if self.restored {
+            return;
+        }
+        if let Err(err) = self.restore_files() {
+            report::warn(format!("Failed to restore assets/: {err:#}"));
+        }
+    }
+}
+
+/// Look up a brand by name.
+pub fn find_brand<'a>(brands: &'a [BrandConfig], name: &str) -> Result<&'a BrandConfig> {
+    if brands.is_empty() {
+        bail!("--brand requires a brands list in config");
+    }
+    brands
+        .iter()
+        .find(|brand| brand.name == name)
+        .with_context(|| {
+            format!(
+                "Unknown brand '{}' (available: {})",
+                name,
+                brands
+                    .iter()
+                    .map(|brand| brand.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        })
+}
+
+/// Output directory for `--all-brands` (default: `build/brands/<name>`).
+pub fn output_dir(project_dir: &Path, brand: &BrandConfig) -> PathBuf {
+    match brand.output_dir.as_deref().map(str::trim) {
+        Some(dir) if !dir.is_empty() => project_dir.join(dir),
+        _ => project_dir.join("build").join("brands").join(&brand.name),
+    }
+}
+
+/// Copy the project into `output_dir`, leaving out VCS/build state and every
+/// brand output directory. An existing `output_dir` is replaced.
+pub fn copy_project(project_dir: &Path, output_dir: &Path, excluded: &[PathBuf]) -> Result<()> {
+    let project = project_dir
+        .canonicalize()
+        .with_context(|| format!("Failed to resolve: {}", project_dir.display()))?;
+    if output_dir.exists() {
+        let output = output_dir.canonicalize()?;
+        if project.starts_with(&output) {
+            bail!(
+                "Brand output directory {} contains the project itself",
+                output_dir.display()
+            );
+        }
+        remove_dir_all_with_retry(output_dir)?;
+    }
+
+    let walker = WalkDir::new(project_dir).into_iter().filter_entry(|entry| {
+        let path = entry.path();
+        if excluded.iter().any(|dir| path == dir) {
+            return false;
+        }
+        let top_level = path.parent() == Some(project_dir);
+        let name = entry.file_name().to_string_lossy();
+        !(top_level && SKIP_DIRS.contains(&name.as_ref()))
+    });
+    for entry in walker {
+        let entry = entry?;
+        let rel = entry.path().strip_prefix(project_dir)?;
+        let dst = output_dir.join(rel);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&dst)
+                .with_context(|| format!("Failed to create dir: {}", dst.display()))?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &dst).with_context(|| {
+                format!(
+                    "Failed to copy {} -> {}",
+                    entry.path().display(),
+                    dst.display()
+                )
+            })?;
+        }
+    }
+    log!("✓ Project copied to: {}", output_dir.display());
+    Ok(())
+}
+
+/// Copy the brand's asset directory over `assets/`. With `in_place`, the
+/// project's own assets are backed up first and the returned backup puts
+/// them back, so neither the tree nor the next brand sees this brand's files.
+pub fn copy_assets(
+    project_dir: &Path,
+    assets_dir: &str,
+    in_place: bool,
+) -> Result<Option<AssetsBackup>> {
+    let src = project_dir.join(assets_dir);
+    if !src.is_dir() {
+        bail!("Brand assets directory not found: {}", src.display());
+    }
+    let backup = if in_place {
+        Some(AssetsBackup::create(project_dir)?)
+    } else {
+        None
+    };
+    let mut count = 0;
+    copy_tree(&src, &project_dir.join("assets"), |target| {
+        report::file(FileAction::Copied, target);
+        count += 1;
+    })?;
+    log!(
+        "✓ Brand assets copied ({count} file(s)) from: {}",
+        src.display()
+    );
+    Ok(backup)
+}
+
+/// Copy every file under `src` to the same relative path under `dst`,
+/// calling `copied` with each target.
+fn copy_tree(src: &Path, dst: &Path, mut copied: impl FnMut(&Path)) -> Result<()> {
+    for entry in WalkDir::new(src) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let target = dst.join(entry.path().strip_prefix(src)?);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+        }
+        fs::copy(entry.path(), &target).with_context(|| {
+            format!(
+                "Failed to copy {} -> {}",
+                entry.path().display(),
+                target.display()
+            )
+        })?;
+        copied(&target);
+    }
+    Ok(())
+}
+
+/// Set `name`/`short_name` in web/manifest.json.
+pub fn apply_web_label(web_dir: &Path, label: &str) -> Result<()> {
+    update_web_manifest(&web_dir.join("manifest.json"), |manifest| {
+        for key in ["name", "short_name"] {
+            manifest.insert(key.to_string(), Value::String(label.to_string()));
+        }
+    })?;
+    log!("✓ Web manifest name set to: {label}");
+    Ok(())
+}
+
+/// Set the window title passed to `window.Create(L"...")` in
+/// windows/runner/main.cpp.
+pub fn apply_windows_label(windows_dir: &Path, label: &str) -> Result<()> {
+    let path = windows_dir.join("runner").join("main.cpp");
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read: {}", path.display()))?;
+    let marker = "window.Create(L\"";
+    let Some(start) = content.find(marker).map(|pos| pos + marker.len()) else {
+        bail!("window.Create(L\"...\") not found in: {}", path.display());
+    };
+    let end = content[start..]
+        .find('"')
+        .map(|pos| start + pos)
+        .with_context(|| format!("Unterminated window title in: {}", path.display()))?;
+    let escaped = label.replace('\\', "\\\\").replace('"', "\\\"");
+    let updated = format!("{}{}{}", &content[..start], escaped, &content[end..]);
+    fs::write(&path, updated).with_context(|| format!("Failed to write: {}", path.display()))?;
+    report::file(FileAction::Patched, &path);
+    log!("✓ Windows window title set to: {label}");
+    Ok(())
+}
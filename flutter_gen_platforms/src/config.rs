@@ -1,12 +1,14 @@
 use anyhow::{bail, Context, Result};
 use serde::Deserialize;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 use which::which;
 
-#[derive(Debug, Deserialize)]
+use crate::android::DEFAULT_LOCALE;
+
+#[derive(Debug, Clone, Deserialize)]
 #[allow(dead_code)]
 pub struct Config {
     pub project_name: String,
@@ -19,15 +21,126 @@ pub struct Config {
     #[serde(default)]
     pub pubspec: Option<PubspecConfig>,
     #[serde(default)]
+    pub flutter: Option<FlutterConfig>,
+    #[serde(default)]
     pub platforms_dir: Option<String>,
     #[serde(default)]
     pub create: FlutterCreateConfig,
     pub android: AndroidConfig,
     pub ios: Option<IosConfig>,
     pub windows: Option<WindowsConfig>,
+    #[serde(default)]
+    pub deep_links: Option<DeepLinksConfig>,
+    #[serde(default)]
+    pub firebase: Option<FirebaseConfig>,
+    #[serde(default)]
+    pub patches: Vec<PatchConfig>,
+    #[serde(default)]
+    pub preserve: PreserveConfig,
+    #[serde(default)]
+    pub brands: Vec<BrandConfig>,
+    #[serde(default)]
+    pub hooks: HooksConfig,
+}
+
+impl Config {
+    /// Override the app identity with a brand's values. Must run before
+    /// `expand_config` so derived values (namespace) follow the brand.
+    pub fn apply_brand(&mut self, brand: &BrandConfig) {
+        if let Some(value) = &brand.project_name {
+            self.project_name = value.clone();
+        }
+        if let Some(value) = &brand.application_id {
+            self.android.template_vars.application_id = Some(value.clone());
+        }
+        if let Some(value) = &brand.platforms_dir {
+            self.platforms_dir = Some(value.clone());
+        }
+        if let (Some(profile), Some(firebase)) = (&brand.firebase_profile, &mut self.firebase) {
+            firebase.default_profile = Some(profile.clone());
+        }
+        if let Some(label) = &brand.label {
+            // Translated names would otherwise keep the base app's name.
+            for values in self.android.strings.values_mut() {
+                if let Some(name) = values.get_mut("app_name") {
+                    *name = label.clone();
+                }
+            }
+            self.android
+                .strings
+                .entry(DEFAULT_LOCALE.to_string())
+                .or_default()
+                .insert("app_name".to_string(), label.clone());
+        }
+    }
+}
+
+/// Flutter SDK the project must be generated with.
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlutterConfig {
+    /// Semver requirement, e.g. `">=3.24.0, <3.27.0"`. Prerelease SDKs
+    /// (`3.27.0-0.1.pre`) are matched by their release version unless the
+    /// requirement names a prerelease itself.
+    pub version: String,
+    /// `stable`, `beta` or `master`.
+    #[serde(default)]
+    pub channel: Option<String>,
+}
+
+/// Shell commands run around the generation steps, in the project dir. See
+/// `hooks::hook_env` for the environment they receive.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct HooksConfig {
+    #[serde(default)]
+    pub before_create: Vec<String>,
+    #[serde(default)]
+    pub after_create: Vec<String>,
+    #[serde(default)]
+    pub android: PlatformHooks,
+    #[serde(default)]
+    pub web: PlatformHooks,
+    #[serde(default)]
+    pub windows: PlatformHooks,
+    /// Run once everything (including patches) has been applied.
+    #[serde(default)]
+    pub after_generate: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct PlatformHooks {
+    #[serde(default)]
+    pub before: Vec<String>,
+    #[serde(default)]
+    pub after: Vec<String>,
+}
+
+/// One white-label variant of the app. Unset fields keep the base config.
+#[derive(Debug, Clone, Deserialize)]
+pub struct BrandConfig {
+    /// Identifier used by `--brand` and the default output directory.
+    pub name: String,
+    #[serde(default)]
+    pub project_name: Option<String>,
+    #[serde(default)]
+    pub application_id: Option<String>,
+    /// App label (Android `app_name`, web manifest name, Windows title).
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Overlay directory replacing `platforms_dir`.
+    #[serde(default)]
+    pub platforms_dir: Option<String>,
+    /// Directory whose contents are copied over `assets/`.
+    #[serde(default)]
+    pub assets_dir: Option<String>,
+    /// Firebase profile to install for this brand.
+    #[serde(default)]
+    pub firebase_profile: Option<String>,
+    /// Output directory for `--all-brands` (default: `build/brands/<name>`).
+    #[serde(default)]
+    pub output_dir: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default)]
 #[allow(dead_code)]
 pub struct PubspecConfig {
     #[serde(default)]
@@ -42,23 +155,179 @@ pub struct PubspecConfig {
     pub repository: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct FlutterCreateConfig {
     #[serde(default)]
     pub platforms: Option<Vec<String>>,
     #[serde(default)]
     pub android_language: Option<String>,
+    /// `--template`: app (default), module, package, plugin or plugin_ffi.
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub ios_language: Option<String>,
+    #[serde(default)]
+    pub empty: bool,
+    #[serde(default)]
+    pub no_pub: bool,
+    #[serde(default)]
+    pub offline: bool,
+    /// `--sample`: id of a Flutter API sample to create the app from.
+    #[serde(default)]
+    pub sample: Option<String>,
+    /// Platforms a plugin/plugin_ffi template supports (its `--platforms`).
+    /// Falls back to `platforms`, which selects the example app dirs to process.
+    #[serde(default)]
+    pub plugin_platforms: Option<Vec<String>>,
+}
+
+/// Values accepted by `flutter create --template`.
+const FLUTTER_TEMPLATES: &[&str] = &["app", "module", "package", "plugin", "plugin_ffi"];
+
+impl FlutterCreateConfig {
+    pub fn template(&self) -> &str {
+        self.template.as_deref().unwrap_or("app")
+    }
+
+    pub fn is_plugin(&self) -> bool {
+        matches!(self.template(), "plugin" | "plugin_ffi")
+    }
+
+    /// Whether the template generates an app (plugins generate one in example/).
+    pub fn has_app(&self) -> bool {
+        self.template() == "app" || self.is_plugin()
+    }
+
+    /// Directory of the generated app whose platform dirs get processed: the
+    /// project itself, or `example/` for plugins. `None` for module and
+    /// package templates, which have no platform dirs of their own.
+    pub fn app_dir(&self, project_dir: &Path) -> Option<PathBuf> {
+        if self.is_plugin() {
+            Some(project_dir.join("example"))
+        } else if self.has_app() {
+            Some(project_dir.to_path_buf())
+        } else {
+            None
+        }
+    }
+
+    fn validate(&self) -> Result<()> {
+        let template = self.template();
+        if !FLUTTER_TEMPLATES.contains(&template) {
+            bail!(
+                "Unknown create.template '{template}' (expected one of: {})",
+                FLUTTER_TEMPLATES.join(", ")
+            );
+        }
+        if let Some(value) = self
+            .android_language
+            .as_deref()
+            .filter(|value| !matches!(*value, "java" | "kotlin"))
+        {
+            bail!("create.android_language must be java or kotlin, got '{value}'");
+        }
+        if let Some(value) = self
+            .ios_language
+            .as_deref()
+            .filter(|value| !matches!(*value, "objc" | "swift"))
+        {
+            bail!("create.ios_language must be objc or swift, got '{value}'");
+        }
+        if template != "app" && (self.empty || self.sample.is_some()) {
+            bail!("create.empty and create.sample only apply to the app template");
+        }
+        if self.empty && self.sample.is_some() {
+            bail!("create.empty and create.sample cannot be combined");
+        }
+        if self.plugin_platforms.is_some() && !self.is_plugin() {
+            bail!("create.plugin_platforms requires template = \"plugin\" or \"plugin_ffi\"");
+        }
+        Ok(())
+    }
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 pub struct AndroidConfig {
     #[serde(default)]
     pub gradle_wrapper: AndroidGradleWrapperConfig,
     #[serde(default)]
     pub template_vars: AndroidTemplateVars,
+    /// Locale → string resources. The `default` locale is written to
+    /// `res/values/strings.xml`, every other locale (`fr`, `pt-BR`, `zh-Hans`)
+    /// to `res/values-<qualifier>/strings.xml`. `app_name` also becomes the
+    /// iOS display name when ios/Runner exists.
+    #[serde(default)]
+    pub strings: BTreeMap<String, BTreeMap<String, String>>,
+    #[serde(default)]
+    pub network_security: Option<NetworkSecurityConfig>,
+    #[serde(default)]
+    pub proguard: Option<ProguardConfig>,
+    #[serde(default)]
+    pub lint: LintConfig,
+}
+
+/// Checks run on the generated android/ project. Rules listed in `ignore`
+/// are skipped; with `warn_only`, findings are reported without failing.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct LintConfig {
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    #[serde(default)]
+    pub warn_only: bool,
+}
+
+/// R8 settings: per-build-type minify/shrink flags plus the keep rules
+/// assembled into `app/proguard-rules.pro`.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ProguardConfig {
+    #[serde(default)]
+    pub build_types: BTreeMap<String, BuildTypeMinifyConfig>,
+    /// Named rule sets, e.g. `gson`, `okhttp`, `play_core`.
+    #[serde(default)]
+    pub presets: Vec<String>,
+    /// Raw keep-rule snippets appended after the presets.
+    #[serde(default)]
+    pub rules: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct BuildTypeMinifyConfig {
+    #[serde(default)]
+    pub minify: bool,
+    #[serde(default)]
+    pub shrink_resources: bool,
+}
+
+/// Source for `res/xml/network_security_config.xml`.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct NetworkSecurityConfig {
+    /// Domains (including subdomains) allowed to use cleartext HTTP.
+    #[serde(default)]
+    pub cleartext_domains: Vec<String>,
+    #[serde(default)]
+    pub pins: Vec<CertificatePinConfig>,
+    /// Extra trust anchors for debug builds only: `user`, `system` or `@raw/<name>`.
+    #[serde(default)]
+    pub debug_trust_anchors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CertificatePinConfig {
+    pub domain: String,
+    #[serde(default = "default_true")]
+    pub include_subdomains: bool,
+    /// Pin expiry as `YYYY-MM-DD`; Android stops enforcing the pins after it.
+    #[serde(default)]
+    pub expiration: Option<String>,
+    /// Base64 SHA-256 digests of the certificates' SubjectPublicKeyInfo.
+    pub sha256: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct AndroidTemplateVars {
     #[serde(default)]
     pub namespace: Option<String>,
@@ -72,16 +341,16 @@ pub struct AndroidTemplateVars {
     pub store_file: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default)]
 pub struct AndroidGradleWrapperConfig {
     pub distribution_url: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Deserialize)]
 #[allow(dead_code)]
 pub struct IosConfig {}
 
-#[derive(Debug, Deserialize, Default)]
+#[derive(Debug, Clone, Deserialize, Default)]
 #[allow(dead_code)]
 pub struct WindowsConfig {
     #[serde(default)]
@@ -92,6 +361,122 @@ pub struct WindowsConfig {
     pub window_height: Option<u32>,
 }
 
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct DeepLinksConfig {
+    #[serde(default)]
+    pub links: Vec<DeepLinkConfig>,
+    /// SHA-256 signing certificate fingerprints for `assetlinks.json`. When
+    /// empty, the fingerprint is read from the release keystore with keytool.
+    #[serde(default)]
+    pub sha256_cert_fingerprints: Vec<String>,
+    /// Apple developer team id; `apple-app-site-association` is only written
+    /// when set.
+    #[serde(default)]
+    pub apple_team_id: Option<String>,
+    /// iOS bundle id (defaults to the Android application id).
+    #[serde(default)]
+    pub ios_bundle_id: Option<String>,
+    /// Output directory for `assetlinks.json` and `apple-app-site-association`,
+    /// relative to the project dir (default: `.well-known`).
+    #[serde(default)]
+    pub well_known_dir: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeepLinkConfig {
+    #[serde(default = "default_deep_link_schemes")]
+    pub schemes: Vec<String>,
+    #[serde(default)]
+    pub hosts: Vec<String>,
+    #[serde(default)]
+    pub path_prefixes: Vec<String>,
+}
+
+fn default_deep_link_schemes() -> Vec<String> {
+    vec!["https".to_string()]
+}
+
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct FirebaseConfig {
+    /// Profile used when `--firebase-profile` is not given.
+    #[serde(default)]
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, FirebaseProfileConfig>,
+}
+
+/// Paths (relative to the project dir) of one profile's Firebase files.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct FirebaseProfileConfig {
+    #[serde(default)]
+    pub google_services_json: Option<String>,
+    #[serde(default)]
+    pub google_service_info_plist: Option<String>,
+}
+
+/// Glob patterns, relative to each platform dir, for local files kept across
+/// regeneration (e.g. `local.properties`, `.idea/**`).
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct PreserveConfig {
+    #[serde(default)]
+    pub android: Vec<String>,
+    #[serde(default)]
+    pub web: Vec<String>,
+    #[serde(default)]
+    pub windows: Vec<String>,
+}
+
+/// A single edit applied to a generated file (path relative to the project dir).
+#[derive(Debug, Clone, Deserialize)]
+pub struct PatchConfig {
+    pub file: String,
+    #[serde(flatten)]
+    pub op: PatchOp,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PatchOp {
+    RegexReplace {
+        pattern: String,
+        replacement: String,
+        #[serde(default = "default_true")]
+        all: bool,
+    },
+    /// `path` is slash-separated, e.g. `manifest/application/activity[@android:name='.MainActivity']`.
+    /// Without `attribute`, the element's text is set.
+    XmlSet {
+        path: String,
+        #[serde(default)]
+        attribute: Option<String>,
+        value: String,
+    },
+    JsonSet {
+        pointer: String,
+        value: serde_json::Value,
+    },
+    PropertiesSet {
+        key: String,
+        value: String,
+    },
+    InsertAfter {
+        anchor: String,
+        line: String,
+    },
+}
+
+impl PatchOp {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatchOp::RegexReplace { .. } => "regex_replace",
+            PatchOp::XmlSet { .. } => "xml_set",
+            PatchOp::JsonSet { .. } => "json_set",
+            PatchOp::PropertiesSet { .. } => "properties_set",
+            PatchOp::InsertAfter { .. } => "insert_after",
+        }
+    }
+}
+
 pub fn load_config(path: &Path) -> Result<Config> {
     match path.extension().and_then(|ext| ext.to_str()) {
         Some("pkl") => load_pkl_config(path),
@@ -101,6 +486,12 @@ pub fn load_config(path: &Path) -> Result<Config> {
             let cfg: Config = toml::from_str(&content).context("Failed to parse config")?;
             Ok(cfg)
         }
+        Some("yaml" | "yml") => {
+            let content = fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config: {}", path.display()))?;
+            let cfg: Config = serde_saphyr::from_str(&content).context("Failed to parse config")?;
+            Ok(cfg)
+        }
         _ => {
             bail!("Unsupported config format: {}", path.display());
         }
@@ -145,21 +536,30 @@ pub fn expand_config(cfg: &mut Config) -> Result<()> {
     if let Some(value) = cfg.platforms_dir.as_ref() {
         cfg.platforms_dir = Some(expand_env_vars(value)?);
     }
+    if let Some(value) = cfg.version.as_ref() {
+        cfg.version = Some(expand_env_vars(value)?);
+    }
     expand_flutter_create_config(&mut cfg.create)?;
 
-    // Derive application_id from org + project_name if not set
+    // Derive application_id from org + project_name if not set; plugin
+    // example apps get flutter create's `_example` suffix.
     if cfg
         .android
         .template_vars
         .application_id
         .as_deref()
-        .map_or(true, |s| s.trim().is_empty())
+        .is_none_or(|s| s.trim().is_empty())
     {
         if let Some(org) = cfg.org.as_ref().map(|v| v.trim()).filter(|v| !v.is_empty()) {
             let org = org.trim_end_matches('.');
+            let suffix = if cfg.create.is_plugin() {
+                "_example"
+            } else {
+                ""
+            };
             cfg.android.template_vars.application_id =
-                Some(format!("{}.{}", org, cfg.project_name));
-        } else {
+                Some(format!("{}.{}{}", org, cfg.project_name, suffix));
+        } else if cfg.create.has_app() {
             bail!("android.template_vars.application_id is required when org is not set");
         }
     }
@@ -168,7 +568,7 @@ pub fn expand_config(cfg: &mut Config) -> Result<()> {
         .template_vars
         .namespace
         .as_deref()
-        .map_or(true, |s| s.trim().is_empty())
+        .is_none_or(|s| s.trim().is_empty())
     {
         cfg.android.template_vars.namespace = cfg.android.template_vars.application_id.clone();
     }
@@ -176,21 +576,50 @@ pub fn expand_config(cfg: &mut Config) -> Result<()> {
     if let Some(value) = cfg.android.gradle_wrapper.distribution_url.as_ref() {
         cfg.android.gradle_wrapper.distribution_url = Some(expand_env_vars(value)?);
     }
+    if let Some(deep_links) = cfg.deep_links.as_mut() {
+        expand_deep_links_config(deep_links)?;
+    }
+    for values in cfg.android.strings.values_mut() {
+        for value in values.values_mut() {
+            *value = expand_env_vars(value)?;
+        }
+    }
 
     Ok(())
 }
 
 fn expand_flutter_create_config(cfg: &mut FlutterCreateConfig) -> Result<()> {
-    if let Some(value) = cfg.android_language.as_ref() {
-        cfg.android_language = Some(expand_env_vars(value)?);
-    }
-    if let Some(platforms) = cfg.platforms.as_ref() {
-        cfg.platforms = Some(
-            platforms
-                .iter()
-                .map(|value| expand_env_vars(value))
-                .collect::<Result<Vec<_>>>()?,
-        );
+    for value in [
+        &mut cfg.android_language,
+        &mut cfg.template,
+        &mut cfg.ios_language,
+        &mut cfg.sample,
+    ]
+    .into_iter()
+    .flatten()
+    {
+        *value = expand_env_vars(value)?;
+    }
+    for platforms in [&mut cfg.platforms, &mut cfg.plugin_platforms]
+        .into_iter()
+        .flatten()
+    {
+        for value in platforms.iter_mut() {
+            *value = expand_env_vars(value)?;
+        }
+    }
+    cfg.validate()
+}
+
+fn expand_deep_links_config(cfg: &mut DeepLinksConfig) -> Result<()> {
+    for value in cfg.sha256_cert_fingerprints.iter_mut() {
+        *value = expand_env_vars(value)?;
+    }
+    if let Some(value) = cfg.apple_team_id.as_ref() {
+        cfg.apple_team_id = Some(expand_env_vars(value)?);
+    }
+    if let Some(value) = cfg.ios_bundle_id.as_ref() {
+        cfg.ios_bundle_id = Some(expand_env_vars(value)?);
     }
     Ok(())
 }
@@ -268,3 +697,58 @@ fn resolve_cmd(command: &str) -> Result<std::path::PathBuf> {
     }
     which(command).with_context(|| format!("command not found in PATH: {command}"))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+project_name = "app"
+[android.template_vars]
+application_id = "com.acme.app"
+[android.strings.default]
+app_name = "App"
+[android.strings.fr]
+app_name = "Appli"
+greeting = "Salut"
+[firebase.profiles.dev]
+[firebase.profiles.red]
+[[brands]]
+name = "red"
+project_name = "red_app"
+application_id = "com.acme.red"
+label = "Red"
+firebase_profile = "red"
+[[brands]]
+name = "plain"
+"#;
+
+    #[test]
+    fn apply_brand_overrides_identity() {
+        let mut cfg: Config = toml::from_str(CONFIG).unwrap();
+        let brand = cfg.brands[0].clone();
+        cfg.apply_brand(&brand);
+        assert_eq!(cfg.project_name, "red_app");
+        assert_eq!(
+            cfg.android.template_vars.application_id.as_deref(),
+            Some("com.acme.red")
+        );
+        assert_eq!(cfg.android.strings["default"]["app_name"], "Red");
+        assert_eq!(cfg.android.strings["fr"]["app_name"], "Red");
+        assert_eq!(cfg.android.strings["fr"]["greeting"], "Salut");
+        assert_eq!(
+            cfg.firebase.unwrap().default_profile.as_deref(),
+            Some("red")
+        );
+    }
+
+    #[test]
+    fn apply_brand_without_overrides() {
+        let mut cfg: Config = toml::from_str(CONFIG).unwrap();
+        let brand = cfg.brands[1].clone();
+        cfg.apply_brand(&brand);
+        assert_eq!(cfg.project_name, "app");
+        assert_eq!(cfg.android.strings["fr"]["app_name"], "Appli");
+        assert_eq!(cfg.firebase.unwrap().default_profile, None);
+    }
+}
@@ -0,0 +1,311 @@
+use anyhow::{bail, Context, Result};
+use serde_json::json;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::{DeepLinkConfig, DeepLinksConfig};
+use crate::manifest::{escape_attr, find_main_activity, MANIFEST_PATH};
+use crate::report::{self, FileAction};
+use crate::utils::resolve_cmd;
+
+/// Add one `<intent-filter>` per configured link to the launcher activity.
+/// Filters with only http(s) schemes get `android:autoVerify="true"`.
+pub fn apply_android_intent_filters(android_dir: &Path, config: &DeepLinksConfig) -> Result<()> {
+    if config.links.is_empty() {
+        return Ok(());
+    }
+
+    let manifest_path = android_dir.join(MANIFEST_PATH);
+    let content = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read: {}", manifest_path.display()))?;
+    let activity = find_main_activity(&content)
+        .with_context(|| format!("No launcher activity found in: {}", manifest_path.display()))?;
+
+    // Indent filters one level deeper than the closing </activity> tag.
+    let body = &content[activity.body.clone()];
+    let close_indent: String = body
+        .rsplit('\n')
+        .next()
+        .unwrap_or("")
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect();
+    let indent = format!("{close_indent}    ");
+
+    let mut filters = String::new();
+    for link in &config.links {
+        filters.push_str(&render_intent_filter(link, &indent)?);
+    }
+
+    let insert_at = activity.body.end - close_indent.len();
+    let updated = format!(
+        "{}{}{}",
+        &content[..insert_at],
+        filters,
+        &content[insert_at..]
+    );
+    fs::write(&manifest_path, updated)
+        .with_context(|| format!("Failed to write: {}", manifest_path.display()))?;
+    report::file(FileAction::Patched, &manifest_path);
+
+    log!(
+        "✓ Android deep link intent filters added ({})",
+        config.links.len()
+    );
+    Ok(())
+}
+
+fn render_intent_filter(link: &DeepLinkConfig, indent: &str) -> Result<String> {
+    if link.schemes.is_empty() {
+        bail!("deep_links.links entries need at least one scheme");
+    }
+    let auto_verify = !link.hosts.is_empty()
+        && link
+            .schemes
+            .iter()
+            .all(|scheme| scheme == "http" || scheme == "https");
+
+    let inner = format!("{indent}    ");
+    let mut out = if auto_verify {
+        format!("{indent}<intent-filter android:autoVerify=\"true\">\n")
+    } else {
+        format!("{indent}<intent-filter>\n")
+    };
+    out.push_str(&format!(
+        "{inner}<action android:name=\"android.intent.action.VIEW\" />\n"
+    ));
+    out.push_str(&format!(
+        "{inner}<category android:name=\"android.intent.category.DEFAULT\" />\n"
+    ));
+    out.push_str(&format!(
+        "{inner}<category android:name=\"android.intent.category.BROWSABLE\" />\n"
+    ));
+    for scheme in &link.schemes {
+        out.push_str(&format!(
+            "{inner}<data android:scheme=\"{}\" />\n",
+            escape_attr(scheme)
+        ));
+    }
+    for host in &link.hosts {
+        out.push_str(&format!(
+            "{inner}<data android:host=\"{}\" />\n",
+            escape_attr(host)
+        ));
+    }
+    for prefix in &link.path_prefixes {
+        out.push_str(&format!(
+            "{inner}<data android:pathPrefix=\"{}\" />\n",
+            escape_attr(prefix)
+        ));
+    }
+    out.push_str(&format!("{indent}</intent-filter>\n"));
+    Ok(out)
+}
+
+/// Write `assetlinks.json` and (with an Apple team id) `apple-app-site-association`
+/// into the configured `.well-known` directory.
+pub fn write_well_known_files(
+    project_dir: &Path,
+    config: &DeepLinksConfig,
+    application_id: &str,
+    keystore: Option<(&Path, &str)>,
+) -> Result<()> {
+    let verified_hosts = config
+        .links
+        .iter()
+        .any(|link| !link.hosts.is_empty() && link.schemes.iter().any(|s| s == "https"));
+    if !verified_hosts {
+        return Ok(());
+    }
+
+    let well_known_dir = project_dir.join(
+        config
+            .well_known_dir
+            .as_deref()
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .unwrap_or(".well-known"),
+    );
+    fs::create_dir_all(&well_known_dir)
+        .with_context(|| format!("Failed to create dir: {}", well_known_dir.display()))?;
+
+    let fingerprints = if config.sha256_cert_fingerprints.is_empty() {
+        match keystore {
+            Some((store, alias)) => read_keystore_fingerprint(store, alias)?
+                .into_iter()
+                .collect(),
+            None => Vec::new(),
+        }
+    } else {
+        config.sha256_cert_fingerprints.clone()
+    };
+
+    if fingerprints.is_empty() {
+        report::warn(
+            "Skipping assetlinks.json: no sha256_cert_fingerprints configured and no keystore fingerprint available",
+        );
+        report::skipped(
+            &well_known_dir.join("assetlinks.json"),
+            "no certificate fingerprint available",
+        );
+    } else {
+        let assetlinks = json!([{
+            "relation": ["delegate_permission/common.handle_all_urls"],
+            "target": {
+                "namespace": "android_app",
+                "package_name": application_id,
+                "sha256_cert_fingerprints": fingerprints,
+            }
+        }]);
+        write_json(&well_known_dir.join("assetlinks.json"), &assetlinks)?;
+    }
+
+    if let Some(team_id) = config.apple_team_id.as_deref() {
+        let bundle_id = config.ios_bundle_id.as_deref().unwrap_or(application_id);
+        let components: Vec<_> = config
+            .links
+            .iter()
+            .filter(|link| link.schemes.iter().any(|s| s == "https"))
+            .flat_map(|link| {
+                if link.path_prefixes.is_empty() {
+                    vec![json!({ "/": "*" })]
+                } else {
+                    link.path_prefixes
+                        .iter()
+                        .map(|prefix| json!({ "/": format!("{}*", prefix) }))
+                        .collect()
+                }
+            })
+            .collect();
+        let aasa = json!({
+            "applinks": {
+                "details": [{
+                    "appIDs": [format!("{team_id}.{bundle_id}")],
+                    "components": components,
+                }]
+            }
+        });
+        write_json(&well_known_dir.join("apple-app-site-association"), &aasa)?;
+    }
+
+    log!(
+        "✓ Deep link verification files written to: {}",
+        well_known_dir.display()
+    );
+    Ok(())
+}
+
+fn write_json(path: &Path, value: &serde_json::Value) -> Result<()> {
+    let content = serde_json::to_string_pretty(value)?;
+    fs::write(path, format!("{content}\n"))
+        .with_context(|| format!("Failed to write: {}", path.display()))?;
+    report::file(FileAction::Created, path);
+    Ok(())
+}
+
+/// Read the SHA-256 certificate fingerprint of `alias` from a keystore using
+/// keytool. Returns `None` when the keystore or `KEYSTORE_PASSWORD` is missing.
+fn read_keystore_fingerprint(store: &Path, alias: &str) -> Result<Option<String>> {
+    if !store.exists() {
+        return Ok(None);
+    }
+    if std::env::var_os("KEYSTORE_PASSWORD").is_none() {
+        return Ok(None);
+    }
+    let keytool = resolve_cmd("keytool")?;
+    let output = Command::new(keytool)
+        .args(["-list", "-v", "-alias", alias, "-keystore"])
+        .arg(store)
+        .arg("-storepass:env")
+        .arg("KEYSTORE_PASSWORD")
+        .output()
+        .context("Failed to run keytool")?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!("keytool failed for {}: {stderr}", store.display());
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().find_map(|line| {
+        line.trim()
+            .strip_prefix("SHA256:")
+            .map(|v| v.trim().to_string())
+    }))
+}
+
+/// Resolve the release keystore: `store_file` relative to android/app (as
+/// Gradle's `file()` would), falling back to the overlay's `keystore.jks`,
+/// which the overlay copy keeps out of android/.
+pub fn resolve_keystore_path(
+    project_dir: &Path,
+    app_dir: &Path,
+    platforms_root: &str,
+    store_file: Option<&str>,
+) -> PathBuf {
+    if let Some(store_file) = store_file {
+        let path = app_dir.join("android").join("app").join(store_file);
+        if path.exists() {
+            return path;
+        }
+    }
+    project_dir
+        .join(platforms_root)
+        .join("android")
+        .join("keystore.jks")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(schemes: &[&str], hosts: &[&str], path_prefixes: &[&str]) -> DeepLinkConfig {
+        let strings = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        DeepLinkConfig {
+            schemes: strings(schemes),
+            hosts: strings(hosts),
+            path_prefixes: strings(path_prefixes),
+        }
+    }
+
+    #[test]
+    fn render_intent_filter_app_link() {
+        let filter = render_intent_filter(
+            &link(&["https"], &["example.com"], &["/app"]),
+            "            ",
+        )
+        .unwrap();
+        assert_eq!(
+            filter,
+            r#"            <intent-filter android:autoVerify="true">
+                <action android:name="android.intent.action.VIEW" />
+                <category android:name="android.intent.category.DEFAULT" />
+                <category android:name="android.intent.category.BROWSABLE" />
+                <data android:scheme="https" />
+                <data android:host="example.com" />
+                <data android:pathPrefix="/app" />
+            </intent-filter>
+"#
+        );
+    }
+
+    #[test]
+    fn render_intent_filter_custom_scheme() {
+        let filter = render_intent_filter(&link(&["myapp"], &["open"], &[]), "").unwrap();
+        assert!(filter.starts_with("<intent-filter>\n"));
+        assert!(filter.contains("    <data android:scheme=\"myapp\" />\n"));
+        // Only http(s) links to a host can be verified.
+        let filter = render_intent_filter(&link(&["https", "myapp"], &["a.com"], &[]), "").unwrap();
+        assert!(!filter.contains("autoVerify"));
+        let filter = render_intent_filter(&link(&["https"], &[], &[]), "").unwrap();
+        assert!(!filter.contains("autoVerify"));
+    }
+
+    #[test]
+    fn render_intent_filter_escapes_and_requires_scheme() {
+        let filter =
+            render_intent_filter(&link(&["https"], &["a.com"], &["/x?a=1&b=\"2\""]), "").unwrap();
+        assert!(filter.contains("android:pathPrefix=\"/x?a=1&amp;b=&quot;2&quot;\""));
+        assert!(render_intent_filter(&link(&[], &["a.com"], &[]), "").is_err());
+    }
+}
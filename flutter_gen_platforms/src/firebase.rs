@@ -0,0 +1,237 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::FirebaseConfig;
+use crate::report::{self, FileAction};
+
+/// Firebase files resolved for the selected profile.
+#[derive(Debug)]
+pub struct FirebaseFiles {
+    pub profile: String,
+    pub google_services_json: Option<PathBuf>,
+    pub google_service_info_plist: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleServicesJson {
+    #[serde(default)]
+    client: Vec<GoogleServicesClient>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleServicesClient {
+    client_info: GoogleServicesClientInfo,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleServicesClientInfo {
+    #[serde(default)]
+    android_client_info: Option<AndroidClientInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AndroidClientInfo {
+    package_name: String,
+}
+
+/// Pick the Firebase profile and check its files before anything is
+/// regenerated. `google-services.json` must contain a client for
+/// `application_id`.
+pub fn resolve_firebase_files(
+    project_dir: &Path,
+    config: &FirebaseConfig,
+    profile: Option<&str>,
+    application_id: &str,
+) -> Result<FirebaseFiles> {
+    if config.profiles.is_empty() {
+        bail!("No firebase profiles configured; add one under firebase.profiles");
+    }
+    let profile = match profile.or(config.default_profile.as_deref()) {
+        Some(profile) => profile.to_string(),
+        None if config.profiles.len() == 1 => config.profiles.keys().next().unwrap().clone(),
+        None => bail!(
+            "Multiple firebase profiles configured ({}); pass --firebase-profile or set firebase.default_profile",
+            config.profiles.keys().cloned().collect::<Vec<_>>().join(", ")
+        ),
+    };
+    let Some(profile_config) = config.profiles.get(&profile) else {
+        bail!(
+            "Unknown firebase profile '{}' (available: {})",
+            profile,
+            config
+                .profiles
+                .keys()
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    };
+
+    let resolve = |value: &Option<String>| -> Result<Option<PathBuf>> {
+        let Some(value) = value.as_deref() else {
+            return Ok(None);
+        };
+        let path = project_dir.join(value);
+        if !path.exists() {
+            bail!("Firebase file not found: {}", path.display());
+        }
+        Ok(Some(path))
+    };
+    let google_services_json = resolve(&profile_config.google_services_json)?;
+    let google_service_info_plist = resolve(&profile_config.google_service_info_plist)?;
+
+    if let Some(path) = &google_services_json {
+        validate_google_services_json(path, application_id)?;
+    }
+
+    Ok(FirebaseFiles {
+        profile,
+        google_services_json,
+        google_service_info_plist,
+    })
+}
+
+fn validate_google_services_json(path: &Path, application_id: &str) -> Result<()> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read: {}", path.display()))?;
+    let parsed: GoogleServicesJson = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse: {}", path.display()))?;
+
+    let packages: Vec<&str> = parsed
+        .client
+        .iter()
+        .filter_map(|client| client.client_info.android_client_info.as_ref())
+        .map(|info| info.package_name.as_str())
+        .collect();
+    if !packages.contains(&application_id) {
+        bail!(
+            "{} has no client for application id '{}' (found: {})",
+            path.display(),
+            application_id,
+            if packages.is_empty() {
+                "none".to_string()
+            } else {
+                packages.join(", ")
+            }
+        );
+    }
+    Ok(())
+}
+
+/// Copy `google-services.json` into android/app/.
+pub fn copy_android_files(project_dir: &Path, files: &FirebaseFiles) -> Result<()> {
+    if let Some(src) = &files.google_services_json {
+        let dst = project_dir
+            .join("android")
+            .join("app")
+            .join("google-services.json");
+        copy_file(src, &dst)?;
+        log!("✓ Firebase ({}) google-services.json copied", files.profile);
+    }
+    Ok(())
+}
+
+/// Copy `GoogleService-Info.plist` into ios/Runner/ when the iOS project exists.
+pub fn copy_ios_files(project_dir: &Path, files: &FirebaseFiles) -> Result<()> {
+    let Some(src) = &files.google_service_info_plist else {
+        return Ok(());
+    };
+    let runner_dir = project_dir.join("ios").join("Runner");
+    if !runner_dir.exists() {
+        report::warn(format!(
+            "Skipping GoogleService-Info.plist: {} not found",
+            runner_dir.display()
+        ));
+        report::skipped(
+            &runner_dir.join("GoogleService-Info.plist"),
+            "ios/Runner not found",
+        );
+        return Ok(());
+    }
+    copy_file(src, &runner_dir.join("GoogleService-Info.plist"))?;
+    log!(
+        "✓ Firebase ({}) GoogleService-Info.plist copied",
+        files.profile
+    );
+    Ok(())
+}
+
+fn copy_file(src: &Path, dst: &Path) -> Result<()> {
+    fs::copy(src, dst)
+        .with_context(|| format!("Failed to copy {} -> {}", src.display(), dst.display()))?;
+    report::file(FileAction::Copied, dst);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::FirebaseProfileConfig;
+
+    fn config(default_profile: Option<&str>, profiles: &[&str]) -> FirebaseConfig {
+        FirebaseConfig {
+            default_profile: default_profile.map(str::to_string),
+            profiles: profiles
+                .iter()
+                .map(|name| {
+                    let profile = FirebaseProfileConfig {
+                        google_services_json: Some(format!("{name}.json")),
+                        google_service_info_plist: None,
+                    };
+                    (name.to_string(), profile)
+                })
+                .collect(),
+        }
+    }
+
+    fn error(config: &FirebaseConfig, profile: Option<&str>) -> String {
+        resolve_firebase_files(Path::new("."), config, profile, "com.acme.app")
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn resolve_firebase_files_profile_selection() {
+        assert!(error(&config(None, &[]), None).contains("No firebase profiles"));
+        assert!(error(&config(None, &["dev", "prod"]), None).contains("dev, prod"));
+        assert!(error(&config(None, &["dev"]), Some("prod")).contains("Unknown firebase profile"));
+        // The selected profile's files must exist.
+        assert!(error(&config(None, &["dev"]), None).contains("dev.json"));
+        assert!(error(&config(Some("prod"), &["dev", "prod"]), None).contains("prod.json"));
+        assert!(error(&config(Some("prod"), &["dev", "prod"]), Some("dev")).contains("dev.json"));
+    }
+
+    #[test]
+    fn validate_google_services_json_package() {
+        let dir = std::env::temp_dir().join(format!(
+            "flutter_gen_platforms_firebase_{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("google-services.json");
+        fs::write(
+            &path,
+            r#"{"client": [
+                {"client_info": {"android_client_info": {"package_name": "com.acme.app"}}},
+                {"client_info": {"android_client_info": {"package_name": "com.acme.other"}}},
+                {"client_info": {}}
+            ]}"#,
+        )
+        .unwrap();
+        let result = validate_google_services_json(&path, "com.acme.app");
+        let err = validate_google_services_json(&path, "com.acme.missing").unwrap_err();
+        fs::write(&path, "{}").unwrap();
+        let empty = validate_google_services_json(&path, "com.acme.app").unwrap_err();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_ok());
+        assert!(
+            err.to_string()
+                .contains("found: com.acme.app, com.acme.other"),
+            "{err}"
+        );
+        assert!(empty.to_string().contains("found: none"), "{empty}");
+    }
+}
@@ -0,0 +1,195 @@
+use anyhow::{bail, Context, Result};
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use serde_json::Value;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::config::FlutterConfig;
+use crate::report;
+use crate::utils::resolve_cmd;
+
+const FLUTTER_BIN: &str = if cfg!(windows) {
+    "flutter.bat"
+} else {
+    "flutter"
+};
+
+/// The fields of `flutter --version --machine` that are checked.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct InstalledFlutter {
+    framework_version: String,
+    channel: String,
+}
+
+/// Resolve `command`, preferring the project's FVM-pinned SDK when the
+/// default `flutter` is requested.
+pub fn resolve_flutter_cmd(command: &str, project_dir: &Path) -> Result<PathBuf> {
+    let fvm = match command {
+        "flutter" => fvm_flutter(project_dir)?,
+        _ => None,
+    };
+    if let Some(path) = fvm {
+        log!("Using FVM Flutter SDK: {}", path.display());
+        return Ok(path);
+    }
+    resolve_cmd(command)
+}
+
+/// The flutter binary of the project's FVM SDK: the `.fvm/flutter_sdk` link,
+/// or the pinned version in the FVM cache.
+fn fvm_flutter(project_dir: &Path) -> Result<Option<PathBuf>> {
+    let link = project_dir
+        .join(".fvm")
+        .join("flutter_sdk")
+        .join("bin")
+        .join(FLUTTER_BIN);
+    if link.exists() {
+        return Ok(Some(link));
+    }
+    let Some(version) = fvm_pinned_version(project_dir)? else {
+        return Ok(None);
+    };
+    for cache in fvm_cache_dirs() {
+        let path = cache
+            .join("versions")
+            .join(&version)
+            .join("bin")
+            .join(FLUTTER_BIN);
+        if path.exists() {
+            return Ok(Some(path));
+        }
+    }
+    report::warn(format!(
+        "FVM pins Flutter {version} but it is not installed (run `fvm install`); using flutter from PATH"
+    ));
+    Ok(None)
+}
+
+/// Version pinned by `.fvmrc` (FVM 3) or `.fvm/fvm_config.json` (FVM 2).
+fn fvm_pinned_version(project_dir: &Path) -> Result<Option<String>> {
+    for (path, key) in [
+        (project_dir.join(".fvmrc"), "flutter"),
+        (
+            project_dir.join(".fvm").join("fvm_config.json"),
+            "flutterSdkVersion",
+        ),
+    ] {
+        if !path.exists() {
+            continue;
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read: {}", path.display()))?;
+        let value: Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse: {}", path.display()))?;
+        let version = value
+            .get(key)
+            .and_then(Value::as_str)
+            .with_context(|| format!("No \"{key}\" version in: {}", path.display()))?;
+        return Ok(Some(version.to_string()));
+    }
+    Ok(None)
+}
+
+/// FVM cache roots, most specific first.
+fn fvm_cache_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = ["FVM_CACHE_PATH", "FVM_HOME"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .map(PathBuf::from)
+        .collect();
+    if let Some(local) = env::var_os("LOCALAPPDATA") {
+        dirs.push(PathBuf::from(local).join("fvm"));
+    }
+    if let Some(home) = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE")) {
+        dirs.push(PathBuf::from(home).join("fvm"));
+    }
+    dirs
+}
+
+/// Compare `flutter --version --machine` against the `flutter` config
+/// section. A mismatch fails the run unless `allow_mismatch` is set.
+pub fn check_flutter_version(
+    flutter_cmd: &Path,
+    config: &FlutterConfig,
+    allow_mismatch: bool,
+) -> Result<()> {
+    let installed = query_version(flutter_cmd)?;
+    report::flutter_sdk(&installed.framework_version, &installed.channel);
+
+    let mut mismatches = Vec::new();
+    let range = &config.version;
+    let req = VersionReq::parse(range)
+        .with_context(|| format!("Invalid flutter.version range: {range}"))?;
+    let version = Version::parse(&installed.framework_version).with_context(|| {
+        format!(
+            "Unexpected Flutter version: {}",
+            installed.framework_version
+        )
+    })?;
+    // Beta and master report prereleases such as `3.27.0-0.1.pre`, which a
+    // range without a prerelease of its own never matches; compare those
+    // by their release version instead.
+    let compared = if version.pre.is_empty() || req.comparators.iter().any(|c| !c.pre.is_empty()) {
+        version.clone()
+    } else {
+        Version::new(version.major, version.minor, version.patch)
+    };
+    if !req.matches(&compared) {
+        mismatches.push(format!(
+            "Flutter {version} does not satisfy flutter.version \"{range}\""
+        ));
+    }
+    if let Some(channel) = config
+        .channel
+        .as_deref()
+        .filter(|channel| *channel != installed.channel)
+    {
+        mismatches.push(format!(
+            "Flutter is on the {} channel, config requires {channel}",
+            installed.channel
+        ));
+    }
+
+    if mismatches.is_empty() {
+        log!(
+            "✓ Flutter {} ({}) matches config",
+            installed.framework_version,
+            installed.channel
+        );
+        return Ok(());
+    }
+    if !allow_mismatch {
+        bail!(
+            "{}; pass --allow-flutter-mismatch to generate anyway",
+            mismatches.join("; ")
+        );
+    }
+    for mismatch in mismatches {
+        report::warn(mismatch);
+    }
+    Ok(())
+}
+
+fn query_version(flutter_cmd: &Path) -> Result<InstalledFlutter> {
+    let output = Command::new(flutter_cmd)
+        .args(["--version", "--machine"])
+        .output()
+        .context("Failed to run flutter --version --machine")?;
+    if !output.status.success() {
+        bail!(
+            "flutter --version --machine failed with status: {}",
+            output.status
+        );
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // Flutter may print lock or upgrade notices before the JSON.
+    let json = stdout
+        .find('{')
+        .map(|start| &stdout[start..])
+        .context("No JSON in flutter --version --machine output")?;
+    serde_json::from_str(json).context("Failed to parse flutter --version --machine output")
+}
@@ -0,0 +1,285 @@
+use std::ops::Range;
+
+/// Path of the app module's Kotlin DSL build script, relative to android/.
+pub const APP_GRADLE_PATH: &str = "app/build.gradle.kts";
+
+/// Find the body of the first block named `name` (e.g. `buildTypes { ... }`)
+/// within `range`. The returned range excludes the braces. Names inside
+/// strings and comments are not matched.
+pub fn find_block(content: &str, name: &str, range: Range<usize>) -> Option<Range<usize>> {
+    let mut i = range.start;
+    while i < range.end {
+        if content[i..range.end].starts_with(name) {
+            let preceded_ok = content[..i]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !is_ident_char(c));
+            let after = i + name.len();
+            let rest = &content[after..range.end];
+            let brace_offset = rest.find(|c: char| !c.is_whitespace());
+            if let Some(brace_offset) =
+                brace_offset.filter(|offset| preceded_ok && rest[*offset..].starts_with('{'))
+            {
+                let body_start = after + brace_offset + 1;
+                let body_end = matching_brace(content, body_start)?;
+                if body_end > range.end {
+                    return None;
+                }
+                return Some(body_start..body_end);
+            }
+        }
+        i = match skip_literal(content, i) {
+            Some(next) => next,
+            None => i + content[i..].chars().next().map_or(1, char::len_utf8),
+        };
+    }
+    None
+}
+
+/// Find a build type block inside `buildTypes`, accepting `release { }`,
+/// `getByName("release") { }` and `create("staging") { }`.
+pub fn find_build_type(
+    content: &str,
+    build_types: Range<usize>,
+    name: &str,
+) -> Option<Range<usize>> {
+    find_block(content, name, build_types.clone())
+        .or_else(|| {
+            find_block(
+                content,
+                &format!("getByName(\"{name}\")"),
+                build_types.clone(),
+            )
+        })
+        .or_else(|| find_block(content, &format!("create(\"{name}\")"), build_types))
+}
+
+/// Index of the `}` closing the block whose body starts at `body_start`.
+/// Strings and comments are skipped.
+fn matching_brace(content: &str, body_start: usize) -> Option<usize> {
+    let bytes = content.as_bytes();
+    let mut depth = 1usize;
+    let mut i = body_start;
+    while i < bytes.len() {
+        if let Some(next) = skip_literal(content, i) {
+            i = next;
+            continue;
+        }
+        match bytes[i] {
+            b'{' => depth += 1,
+            b'}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// When a string or comment starts at `i`, the index just past it (an
+/// unterminated one runs to the end). `"..."`, `'...'`, triple-quoted
+/// strings, `//` and `/* */` comments are recognised.
+fn skip_literal(content: &str, i: usize) -> Option<usize> {
+    let rest = &content[i..];
+    // Index just past `close`, searched from `from` within `rest`.
+    let past = |from: usize, close: &str| {
+        rest[from..]
+            .find(close)
+            .map_or(content.len(), |pos| i + from + pos + close.len())
+    };
+    if rest.starts_with("\"\"\"") {
+        return Some(past(3, "\"\"\""));
+    }
+    if rest.starts_with("'''") {
+        return Some(past(3, "'''"));
+    }
+    if rest.starts_with("//") {
+        // Leave the newline to the caller.
+        return Some(rest.find('\n').map_or(content.len(), |pos| i + pos));
+    }
+    if rest.starts_with("/*") {
+        return Some(past(2, "*/"));
+    }
+    let quote = rest.as_bytes()[0];
+    if quote != b'"' && quote != b'\'' {
+        return None;
+    }
+    let bytes = content.as_bytes();
+    let mut j = i + 1;
+    while j < bytes.len() && bytes[j] != quote && bytes[j] != b'\n' {
+        if bytes[j] == b'\\' {
+            j += 1;
+        }
+        j += 1;
+    }
+    Some((j + 1).min(bytes.len()))
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '.'
+}
+
+/// Set `key = value` inside a block body, replacing an existing assignment.
+/// Returns the new content.
+pub fn set_property(content: &str, body: Range<usize>, key: &str, value: &str) -> String {
+    upsert_line(
+        content,
+        body,
+        |trimmed| {
+            trimmed
+                .strip_prefix(key)
+                .is_some_and(|rest| rest.starts_with([' ', '\t', '=']))
+        },
+        &format!("{key} = {value}"),
+    )
+}
+
+/// Set a call-style entry such as `proguardFiles(...)` inside a block body,
+/// replacing an existing call to `name`. Returns the new content.
+pub fn set_call(content: &str, body: Range<usize>, name: &str, args: &str) -> String {
+    let prefix = format!("{name}(");
+    upsert_line(
+        content,
+        body,
+        |trimmed| trimmed.starts_with(&prefix),
+        &format!("{name}({args})"),
+    )
+}
+
+/// Replace the first line of `body` matched by `matches` (given the line
+/// without leading whitespace) with `text`, keeping its indentation, or
+/// append `text` as the last line of the block.
+fn upsert_line(
+    content: &str,
+    body: Range<usize>,
+    matches: impl Fn(&str) -> bool,
+    text: &str,
+) -> String {
+    let body_text = &content[body.clone()];
+    let mut offset = 0;
+    for line in body_text.split_inclusive('\n') {
+        let trimmed = line.trim_start();
+        if matches(trimmed) {
+            let indent = &line[..line.len() - trimmed.len()];
+            let line_start = body.start + offset;
+            let line_end = line_start + line.trim_end_matches(['\r', '\n']).len();
+            return format!(
+                "{}{indent}{text}{}",
+                &content[..line_start],
+                &content[line_end..]
+            );
+        }
+        offset += line.len();
+    }
+
+    let indent = block_indent(body_text);
+    let insert_at = body.start + body_text.trim_end().len();
+    let closing = &content[insert_at..body.end];
+    format!(
+        "{}\n{indent}{text}{}{}",
+        &content[..insert_at],
+        if closing.contains('\n') { "" } else { "\n" },
+        &content[insert_at..]
+    )
+}
+
+/// Indentation of the first non-blank line of a block body; empty blocks get
+/// one level deeper than their closing brace.
+fn block_indent(body: &str) -> String {
+    if let Some(line) = body.lines().find(|line| !line.trim().is_empty()) {
+        return line.chars().take_while(|c| c.is_whitespace()).collect();
+    }
+    let closing_indent = body.rsplit('\n').next().unwrap_or("");
+    format!("{closing_indent}    ")
+}
+
+/// Read a literal string assigned to `key` in a Gradle script, accepting both
+/// `key = "value"` (Kotlin DSL) and `key 'value'` (Groovy), plus `file("...")`.
+/// Values computed from expressions are ignored.
+pub fn string_value(content: &str, key: &str) -> Option<String> {
+    content.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix(key)?;
+        if !rest.starts_with([' ', '\t', '=', '(']) {
+            return None;
+        }
+        let rest = rest.trim_start().trim_start_matches('=').trim_start();
+        let rest = rest.strip_prefix("file(").unwrap_or(rest);
+        let rest = rest.strip_prefix('(').unwrap_or(rest);
+        let quote = rest.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let value = &rest[1..];
+        let end = value.find(quote)?;
+        Some(value[..end].to_string())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block<'a>(content: &'a str, name: &str) -> Option<&'a str> {
+        find_block(content, name, 0..content.len()).map(|body| &content[body])
+    }
+
+    #[test]
+    fn find_block_nested() {
+        let content = "android {\n    buildTypes {\n        release {\n            isMinifyEnabled = true\n        }\n    }\n}\n";
+        let android = find_block(content, "android", 0..content.len()).unwrap();
+        assert!(content[android.clone()].trim_end().ends_with('}'));
+        assert_eq!(content[android.end..].trim(), "}");
+        let build_types = find_block(content, "buildTypes", android).unwrap();
+        let release = find_build_type(content, build_types, "release").unwrap();
+        assert_eq!(content[release].trim(), "isMinifyEnabled = true");
+    }
+
+    #[test]
+    fn find_block_build_type_forms() {
+        let content = "buildTypes {\n    getByName(\"release\") { a = 1 }\n    create(\"staging\") { b = 2 }\n}";
+        let build_types = find_block(content, "buildTypes", 0..content.len()).unwrap();
+        let release = find_build_type(content, build_types.clone(), "release").unwrap();
+        assert_eq!(content[release].trim(), "a = 1");
+        let staging = find_build_type(content, build_types, "staging").unwrap();
+        assert_eq!(content[staging].trim(), "b = 2");
+    }
+
+    #[test]
+    fn find_block_braces_in_strings() {
+        let content = "android {\n    namespace = \"a}b{\"\n    val c = '}'\n    val t = \"\"\"\n}\n\"\"\"\n    x = 1\n}\ntail";
+        let body = block(content, "android").unwrap();
+        assert!(body.trim_end().ends_with("x = 1"), "{body:?}");
+    }
+
+    #[test]
+    fn find_block_braces_in_comments() {
+        let content = "// android { broken\nandroid {\n    /* } */\n    // }\n    x = 1\n}\n";
+        let body = block(content, "android").unwrap();
+        assert!(body.starts_with("\n    /* } */"), "{body:?}");
+        assert!(body.trim_end().ends_with("x = 1"), "{body:?}");
+    }
+
+    #[test]
+    fn find_block_skips_names_in_strings_and_longer_identifiers() {
+        let content =
+            "val s = \"release {\"\nmyrelease { a = 1 }\nfoo.release { b = 2 }\nrelease { c = 3 }";
+        assert_eq!(block(content, "release").map(str::trim), Some("c = 3"));
+    }
+
+    #[test]
+    fn find_block_respects_range() {
+        let content = "a {\n}\nb {\n    c { x = 1 }\n}\nc { y = 2 }";
+        let a = find_block(content, "a", 0..content.len()).unwrap();
+        assert_eq!(find_block(content, "c", a), None);
+        let b = find_block(content, "b", 0..content.len()).unwrap();
+        let c = find_block(content, "c", b).unwrap();
+        assert_eq!(content[c].trim(), "x = 1");
+    }
+
+    #[test]
+    fn find_block_unterminated() {
+        assert_eq!(block("android {\n    x = \"}\"\n", "android"), None);
+        assert_eq!(block("android {\n    /* }\n", "android"), None);
+    }
+}
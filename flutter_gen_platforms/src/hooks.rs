@@ -0,0 +1,81 @@
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::config::HooksConfig;
+use crate::report;
+
+/// Environment passed to every hook: `FGP_PROJECT_DIR`, `FGP_APP_DIR` and
+/// one `FGP_VAR_<NAME>` per template var (e.g. `FGP_VAR_APPLICATION_ID`).
+pub fn hook_env(
+    project_dir: &Path,
+    app_dir: &Path,
+    template_vars: &HashMap<String, String>,
+) -> Vec<(String, String)> {
+    let absolute = |path: &Path| {
+        path.canonicalize()
+            .unwrap_or_else(|_| path.to_path_buf())
+            .display()
+            .to_string()
+    };
+    let mut env = vec![
+        ("FGP_PROJECT_DIR".to_string(), absolute(project_dir)),
+        ("FGP_APP_DIR".to_string(), absolute(app_dir)),
+    ];
+    let mut vars: Vec<_> = template_vars.iter().collect();
+    vars.sort();
+    for (key, value) in vars {
+        env.push((format!("FGP_VAR_{}", key.to_uppercase()), value.clone()));
+    }
+    env
+}
+
+/// Every configured stage with its commands, in execution order.
+pub fn stages(hooks: &HooksConfig) -> [(&'static str, &[String]); 9] {
+    [
+        ("before_create", &hooks.before_create),
+        ("after_create", &hooks.after_create),
+        ("before_android", &hooks.android.before),
+        ("after_android", &hooks.android.after),
+        ("before_web", &hooks.web.before),
+        ("after_web", &hooks.web.after),
+        ("before_windows", &hooks.windows.before),
+        ("after_windows", &hooks.windows.after),
+        ("after_generate", &hooks.after_generate),
+    ]
+}
+
+/// Run each command of `stage` through the platform shell in `project_dir`,
+/// stopping at the first failure. `FGP_STAGE` names the stage.
+pub fn run_hooks(
+    stage: &str,
+    commands: &[String],
+    project_dir: &Path,
+    env: &[(String, String)],
+) -> Result<()> {
+    for command in commands {
+        log!("→ Hook {stage}: {command}");
+        let mut shell = if cfg!(windows) {
+            let mut shell = Command::new("cmd");
+            shell.arg("/C");
+            shell
+        } else {
+            let mut shell = Command::new("sh");
+            shell.arg("-c");
+            shell
+        };
+        let status = shell
+            .arg(command)
+            .current_dir(project_dir)
+            .envs(env.iter().map(|(key, value)| (key, value)))
+            .env("FGP_STAGE", stage)
+            .stdout(report::child_stdout())
+            .status()
+            .with_context(|| format!("Failed to run {stage} hook: {command}"))?;
+        if !status.success() {
+            bail!("{stage} hook failed ({status}): {command}");
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,610 @@
+use anyhow::{bail, Context, Result};
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::android::read_properties;
+use crate::config::FlutterCreateConfig;
+use crate::gradle::string_value;
+use crate::manifest::{attribute_value, find_start_tag, MANIFEST_PATH};
+use crate::report;
+use crate::utils::{remove_dir_all_with_retry, run_flutter_create};
+
+/// Directories under android/ that only hold build or IDE state.
+const IGNORED_DIRS: &[&str] = &[".gradle", ".idea", ".cxx", ".kotlin", "build"];
+
+/// Files under android/ that are machine-specific or regenerated by Flutter.
+const IGNORED_FILES: &[&str] = &["local.properties", "GeneratedPluginRegistrant.java"];
+
+/// Signing secrets, never copied into the overlay (which gets committed).
+const SECRET_FILES: &[&str] = &["key.properties"];
+const SECRET_EXTENSIONS: &[&str] = &[".jks", ".keystore"];
+
+/// Platform directories recognised by `flutter create --platforms`.
+const KNOWN_PLATFORMS: &[&str] = &["android", "ios", "web", "windows", "linux", "macos"];
+
+#[derive(Args, Debug)]
+pub struct InitArgs {
+    /// Config format to emit
+    #[arg(long, value_enum, default_value = "pkl")]
+    format: InitFormat,
+
+    /// Output file (defaults to app.<format> in the project directory)
+    #[arg(long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Overwrite an existing config file and platforms/android overlay
+    #[arg(long)]
+    force: bool,
+
+    /// Only write the config; don't extract an android overlay
+    #[arg(long)]
+    no_overlay: bool,
+}
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+enum InitFormat {
+    Pkl,
+    Toml,
+    Yaml,
+}
+
+impl InitFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            InitFormat::Pkl => "pkl",
+            InitFormat::Toml => "toml",
+            InitFormat::Yaml => "yaml",
+        }
+    }
+}
+
+/// Starter config emitted by `init`. Mirrors the subset of `Config` that can
+/// be recovered from an existing project.
+#[derive(Debug, Serialize)]
+struct InitConfig {
+    project_name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    org: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    pubspec: InitPubspec,
+    create: InitCreate,
+    android: InitAndroid,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    windows: Option<InitWindows>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct InitPubspec {
+    name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    homepage: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    repository: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct InitCreate {
+    platforms: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    android_language: Option<String>,
+}
+
+#[derive(Debug, Serialize, Default)]
+struct InitAndroid {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gradle_wrapper: Option<InitGradleWrapper>,
+    template_vars: InitTemplateVars,
+}
+
+#[derive(Debug, Serialize)]
+struct InitGradleWrapper {
+    distribution_url: String,
+}
+
+#[derive(Debug, Serialize, Default)]
+struct InitTemplateVars {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    application_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    key_alias: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    store_file: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct InitWindows {
+    enabled: bool,
+    window_width: u32,
+    window_height: u32,
+}
+
+/// Values read from android/app/build.gradle(.kts) and AndroidManifest.xml.
+#[derive(Debug, Default)]
+struct AndroidInfo {
+    namespace: Option<String>,
+    application_id: Option<String>,
+    key_alias: Option<String>,
+    store_file: Option<String>,
+    label: Option<String>,
+}
+
+/// Reverse-engineer a starter config (and android overlay) from an existing
+/// Flutter project.
+pub fn run_init(project_dir: &Path, flutter_cmd: &Path, args: &InitArgs) -> Result<()> {
+    let output = args
+        .output
+        .clone()
+        .unwrap_or_else(|| project_dir.join(format!("app.{}", args.format.extension())));
+    if output.exists() && !args.force {
+        bail!(
+            "Config already exists: {} (use --force to overwrite)",
+            output.display()
+        );
+    }
+
+    let pubspec = read_pubspec(project_dir)?;
+    let android = read_android_info(&project_dir.join("android"))?;
+    let cfg = build_init_config(project_dir, pubspec, &android)?;
+
+    if let Some(label) = &android.label {
+        log!("Detected android label: {label}");
+    }
+
+    if !args.no_overlay && project_dir.join("android").exists() {
+        let overlay_dir = project_dir.join("platforms").join("android");
+        if overlay_dir.exists() && !args.force {
+            bail!(
+                "Overlay directory already exists: {} (use --force to overwrite)",
+                overlay_dir.display()
+            );
+        }
+        let copied = extract_android_overlay(project_dir, flutter_cmd, &cfg, &overlay_dir)?;
+        log!(
+            "Copied {} modified android file(s) to: {}",
+            copied.len(),
+            overlay_dir.display()
+        );
+        for path in &copied {
+            log!("  {}", path.display());
+        }
+    }
+
+    let rendered = render_config(&cfg, args.format)?;
+    fs::write(&output, rendered)
+        .with_context(|| format!("Failed to write config: {}", output.display()))?;
+    log!("Config written to: {}", output.display());
+    Ok(())
+}
+
+fn read_pubspec(project_dir: &Path) -> Result<InitPubspec> {
+    let path = project_dir.join("pubspec.yaml");
+    let content = fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read pubspec: {}", path.display()))?;
+    serde_saphyr::from_str(&content)
+        .with_context(|| format!("Failed to parse pubspec: {}", path.display()))
+}
+
+fn read_android_info(android_dir: &Path) -> Result<AndroidInfo> {
+    let mut info = AndroidInfo::default();
+
+    let gradle_path = ["build.gradle.kts", "build.gradle"]
+        .iter()
+        .map(|name| android_dir.join("app").join(name))
+        .find(|path| path.exists());
+    if let Some(path) = gradle_path {
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read: {}", path.display()))?;
+        info.namespace = string_value(&content, "namespace");
+        info.application_id = string_value(&content, "applicationId");
+        info.key_alias = string_value(&content, "keyAlias");
+        info.store_file = string_value(&content, "storeFile");
+    }
+
+    let manifest_path = android_dir.join(MANIFEST_PATH);
+    if manifest_path.exists() {
+        let content = fs::read_to_string(&manifest_path)
+            .with_context(|| format!("Failed to read: {}", manifest_path.display()))?;
+        // Pre-AGP 7 projects declare the package in the manifest instead of Gradle.
+        if info.application_id.is_none() {
+            info.application_id = find_start_tag(&content, "manifest")
+                .and_then(|range| attribute_value(&content[range], "package"))
+                .map(str::to_string);
+        }
+        info.label = find_start_tag(&content, "application")
+            .and_then(|range| attribute_value(&content[range], "android:label"))
+            .map(str::to_string);
+    }
+
+    Ok(info)
+}
+
+fn build_init_config(
+    project_dir: &Path,
+    pubspec: InitPubspec,
+    android: &AndroidInfo,
+) -> Result<InitConfig> {
+    let project_name = pubspec.name.clone();
+
+    let platforms: Vec<String> = KNOWN_PLATFORMS
+        .iter()
+        .filter(|name| project_dir.join(name).is_dir())
+        .map(|name| name.to_string())
+        .collect();
+
+    let android_dir = project_dir.join("android");
+    let android_language = if android_dir.join("app/src/main/kotlin").exists() {
+        Some("kotlin".to_string())
+    } else if android_dir.join("app/src/main/java").exists() {
+        Some("java".to_string())
+    } else {
+        None
+    };
+
+    // Prefer an org that reproduces the application id via `org.project_name`.
+    let org = android.application_id.as_deref().and_then(|id| {
+        id.strip_suffix(project_name.as_str())
+            .and_then(|prefix| prefix.strip_suffix('.'))
+            .filter(|prefix| !prefix.is_empty())
+            .map(str::to_string)
+    });
+    let application_id = if org.is_some() {
+        None
+    } else {
+        android.application_id.clone()
+    };
+    let namespace = android
+        .namespace
+        .clone()
+        .filter(|ns| Some(ns) != android.application_id.as_ref());
+
+    let distribution_url =
+        read_properties(&android_dir.join("gradle/wrapper/gradle-wrapper.properties"))?
+            .remove("distributionUrl")
+            .map(|distribution_url| InitGradleWrapper { distribution_url });
+
+    let description = match &pubspec.description {
+        Some(description) => Some(description.clone()),
+        None => read_web_manifest_description(project_dir)?,
+    };
+
+    Ok(InitConfig {
+        project_name,
+        org,
+        description,
+        pubspec,
+        create: InitCreate {
+            platforms,
+            android_language,
+        },
+        android: InitAndroid {
+            gradle_wrapper: distribution_url,
+            template_vars: InitTemplateVars {
+                namespace,
+                application_id,
+                key_alias: android.key_alias.clone(),
+                store_file: android.store_file.clone(),
+            },
+        },
+        windows: read_windows_size(project_dir)?.map(|(width, height)| InitWindows {
+            enabled: true,
+            window_width: width,
+            window_height: height,
+        }),
+    })
+}
+
+fn read_web_manifest_description(project_dir: &Path) -> Result<Option<String>> {
+    let path = project_dir.join("web/manifest.json");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read: {}", path.display()))?;
+    let manifest: Value = serde_json::from_str(&content)
+        .with_context(|| format!("Failed to parse: {}", path.display()))?;
+    Ok(manifest
+        .get("description")
+        .and_then(Value::as_str)
+        .map(str::to_string))
+}
+
+/// Read the `Win32Window::Size size(W, H)` line from windows/runner/main.cpp.
+fn read_windows_size(project_dir: &Path) -> Result<Option<(u32, u32)>> {
+    let path = project_dir.join("windows/runner/main.cpp");
+    if !path.exists() {
+        return Ok(None);
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read: {}", path.display()))?;
+    Ok(content.lines().find_map(|line| {
+        let args = line.split("Win32Window::Size size(").nth(1)?;
+        let (args, _) = args.split_once(')')?;
+        let (width, height) = args.split_once(',')?;
+        Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+    }))
+}
+
+/// Run `flutter create` into a scratch directory and copy every android file
+/// that differs from (or is missing in) the fresh output into `overlay_dir`.
+fn extract_android_overlay(
+    project_dir: &Path,
+    flutter_cmd: &Path,
+    cfg: &InitConfig,
+    overlay_dir: &Path,
+) -> Result<Vec<PathBuf>> {
+    let fresh_root =
+        std::env::temp_dir().join(format!("flutter_gen_platforms_init_{}", std::process::id()));
+    if fresh_root.exists() {
+        remove_dir_all_with_retry(&fresh_root)?;
+    }
+
+    // The fresh project is only compared against and then deleted.
+    let create = FlutterCreateConfig {
+        platforms: Some(vec!["android".to_string()]),
+        android_language: cfg.create.android_language.clone(),
+        no_pub: true,
+        ..Default::default()
+    };
+    let result = run_flutter_create(
+        &fresh_root,
+        flutter_cmd,
+        &cfg.project_name,
+        cfg.org.as_deref(),
+        cfg.description.as_deref(),
+        &create,
+    )
+    .and_then(|_| {
+        copy_modified_files(
+            &project_dir.join("android"),
+            &fresh_root.join("android"),
+            overlay_dir,
+            &cfg.android.template_vars,
+        )
+    });
+
+    if fresh_root.exists() {
+        remove_dir_all_with_retry(&fresh_root)?;
+    }
+    result
+}
+
+fn copy_modified_files(
+    android_dir: &Path,
+    fresh_dir: &Path,
+    overlay_dir: &Path,
+    template_vars: &InitTemplateVars,
+) -> Result<Vec<PathBuf>> {
+    let mut copied = Vec::new();
+    let walker = WalkDir::new(android_dir).into_iter().filter_entry(|entry| {
+        let name = entry.file_name().to_string_lossy();
+        !(entry.file_type().is_dir() && IGNORED_DIRS.contains(&name.as_ref()))
+    });
+
+    for entry in walker {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy();
+        if IGNORED_FILES.contains(&name.as_ref()) || name.ends_with(".iml") {
+            continue;
+        }
+
+        let rel = entry.path().strip_prefix(android_dir)?;
+        if SECRET_FILES.contains(&name.as_ref())
+            || SECRET_EXTENSIONS.iter().any(|ext| name.ends_with(ext))
+        {
+            report::warn(format!(
+                "Not copying android/{} into the overlay: it holds signing secrets",
+                rel.display()
+            ));
+            continue;
+        }
+        let current = fs::read(entry.path())
+            .with_context(|| format!("Failed to read: {}", entry.path().display()))?;
+        let fresh = fs::read(fresh_dir.join(rel)).ok();
+        if fresh.is_some_and(|fresh| same_content(&fresh, &current)) {
+            continue;
+        }
+
+        let dst = overlay_dir.join(rel);
+        if let Some(parent) = dst.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Only Kotlin DSL scripts go through `{{var}}` substitution.
+        match std::str::from_utf8(&current) {
+            Ok(text) if name.ends_with(".gradle.kts") => {
+                fs::write(&dst, templatize_gradle(text, template_vars))
+                    .with_context(|| format!("Failed to write: {}", dst.display()))?;
+            }
+            _ => {
+                fs::write(&dst, &current)
+                    .with_context(|| format!("Failed to write: {}", dst.display()))?;
+            }
+        }
+        copied.push(rel.to_path_buf());
+    }
+
+    Ok(copied)
+}
+
+/// Compare file contents, ignoring CRLF vs LF differences.
+fn same_content(a: &[u8], b: &[u8]) -> bool {
+    if a == b {
+        return true;
+    }
+    let strip =
+        |bytes: &[u8]| -> Vec<u8> { bytes.iter().copied().filter(|b| *b != b'\r').collect() };
+    strip(a) == strip(b)
+}
+
+/// Turn literal application id / namespace assignments back into `{{var}}`
+/// placeholders so the overlay follows the config.
+fn templatize_gradle(content: &str, template_vars: &InitTemplateVars) -> String {
+    content
+        .split_inclusive('\n')
+        .map(|line| {
+            let trimmed = line.trim_start();
+            let (key, var) = if trimmed.starts_with("applicationId") {
+                ("applicationId", "application_id")
+            } else if trimmed.starts_with("namespace") {
+                ("namespace", "namespace")
+            } else {
+                return line.to_string();
+            };
+            match string_value(line, key) {
+                Some(value) if !value.is_empty() => {
+                    let explicit = match var {
+                        "application_id" => template_vars.application_id.as_deref(),
+                        _ => template_vars.namespace.as_deref(),
+                    };
+                    // The value is either explicit in the config or derived from org.
+                    if explicit.is_none_or(|v| v == value) {
+                        line.replacen(&value, &format!("{{{{{var}}}}}"), 1)
+                    } else {
+                        line.to_string()
+                    }
+                }
+                _ => line.to_string(),
+            }
+        })
+        .collect()
+}
+
+fn render_config(cfg: &InitConfig, format: InitFormat) -> Result<String> {
+    let header = "Generated by flutter_gen_platforms init";
+    match format {
+        InitFormat::Pkl => {
+            let value = serde_json::to_value(cfg).context("Failed to serialize config")?;
+            Ok(format!("// {header}\n\n{}", render_pkl(&value)))
+        }
+        InitFormat::Toml => {
+            let body = toml::to_string_pretty(cfg).context("Failed to serialize config")?;
+            Ok(format!("# {header}\n\n{body}"))
+        }
+        InitFormat::Yaml => {
+            let body = serde_saphyr::to_string(cfg).context("Failed to serialize config")?;
+            Ok(format!("# {header}\n\n{body}"))
+        }
+    }
+}
+
+fn render_pkl(value: &Value) -> String {
+    let mut out = String::new();
+    if let Value::Object(map) = value {
+        for (key, value) in map {
+            write_pkl_property(&mut out, key, value, 0);
+        }
+    }
+    out
+}
+
+fn write_pkl_property(out: &mut String, key: &str, value: &Value, depth: usize) {
+    let pad = "  ".repeat(depth);
+    match value {
+        Value::Null => {}
+        Value::Object(map) => {
+            out.push_str(&format!("{pad}{key} {{\n"));
+            for (key, value) in map {
+                write_pkl_property(out, key, value, depth + 1);
+            }
+            out.push_str(&format!("{pad}}}\n"));
+        }
+        Value::Array(items) => {
+            out.push_str(&format!("{pad}{key} {{\n"));
+            for item in items {
+                out.push_str(&format!("{pad}  {}\n", pkl_literal(item)));
+            }
+            out.push_str(&format!("{pad}}}\n"));
+        }
+        scalar => out.push_str(&format!("{pad}{key} = {}\n", pkl_literal(scalar))),
+    }
+}
+
+fn pkl_literal(value: &Value) -> String {
+    match value {
+        Value::String(s) => {
+            let escaped = s
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n")
+                .replace('\r', "\\r")
+                .replace('\t', "\\t");
+            format!("\"{escaped}\"")
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const GRADLE: &str = "android {\n    namespace = \"com.acme.app\"\n    defaultConfig {\n        applicationId = \"com.acme.app.dev\"\n        minSdk = flutter.minSdkVersion\n    }\n}\n";
+
+    #[test]
+    fn templatize_gradle_derived_ids() {
+        let vars = InitTemplateVars::default();
+        assert_eq!(
+            templatize_gradle(GRADLE, &vars),
+            GRADLE
+                .replace("\"com.acme.app\"", "\"{{namespace}}\"")
+                .replace("\"com.acme.app.dev\"", "\"{{application_id}}\"")
+        );
+    }
+
+    #[test]
+    fn templatize_gradle_explicit_ids() {
+        let vars = InitTemplateVars {
+            namespace: Some("com.acme.app".to_string()),
+            application_id: Some("com.acme.other".to_string()),
+            ..Default::default()
+        };
+        // Only a literal that matches the config becomes a placeholder.
+        assert_eq!(
+            templatize_gradle(GRADLE, &vars),
+            GRADLE.replace("\"com.acme.app\"", "\"{{namespace}}\"")
+        );
+        let unrelated = "namespaceFoo = 1\n// applicationId\n";
+        assert_eq!(templatize_gradle(unrelated, &vars), unrelated);
+    }
+
+    #[test]
+    fn render_pkl_nested() {
+        let value = json!({
+            "project_name": "app",
+            "org": null,
+            "create": {"platforms": ["android", "web"]},
+            "android": {"template_vars": {"key_alias": "up\"load\\\n", "min_sdk": 21}},
+        });
+        assert_eq!(
+            render_pkl(&value),
+            r#"project_name = "app"
+create {
+  platforms {
+    "android"
+    "web"
+  }
+}
+android {
+  template_vars {
+    key_alias = "up\"load\\\n"
+    min_sdk = 21
+  }
+}
+"#
+        );
+    }
+}
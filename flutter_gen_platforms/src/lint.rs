@@ -0,0 +1,339 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use crate::config::LintConfig;
+use crate::gradle::{find_block, find_build_type, string_value, APP_GRADLE_PATH};
+use crate::manifest::{attribute_value, find_elements, find_start_tag, MANIFEST_PATH};
+use crate::report;
+
+/// Rule ids accepted by `android.lint.ignore`.
+const RULES: &[&str] = &[
+    "missing-exported",
+    "invalid-package",
+    "namespace-mismatch",
+    "com-example",
+    "release-debug-keystore",
+];
+
+/// Components that need `android:exported` when they declare intent filters.
+const COMPONENTS: &[&str] = &["activity", "activity-alias", "service", "receiver"];
+
+/// Directories under android/ that only hold build or IDE state.
+const SKIP_DIRS: &[&str] = &[".gradle", ".idea", ".cxx", ".kotlin", "build"];
+
+/// Extensions scanned for leftover `com.example` identifiers.
+const SCANNED_EXTENSIONS: &[&str] = &["kts", "gradle", "xml", "kt", "java", "properties", "pro"];
+
+/// Reserved Java words, which can't appear in a package name.
+const JAVA_KEYWORDS: &str = "\
+    abstract assert boolean break byte case catch char class const continue \
+    default do double else enum extends false final finally float for goto if \
+    implements import instanceof int interface long native new null package \
+    private protected public return short static strictfp super switch \
+    synchronized this throw throws transient true try void volatile while";
+
+struct Finding {
+    rule: &'static str,
+    message: String,
+}
+
+/// Check the generated android/ project and report findings. Fails the run
+/// unless `config.warn_only` is set.
+pub fn lint_android_project(android_dir: &Path, config: &LintConfig) -> Result<()> {
+    if let Some(unknown) = config
+        .ignore
+        .iter()
+        .find(|rule| !RULES.contains(&rule.as_str()))
+    {
+        bail!(
+            "Unknown lint rule '{}' in android.lint.ignore (available: {})",
+            unknown,
+            RULES.join(", ")
+        );
+    }
+
+    let manifest_path = android_dir.join(MANIFEST_PATH);
+    let manifest = fs::read_to_string(&manifest_path)
+        .with_context(|| format!("Failed to read: {}", manifest_path.display()))?;
+    let gradle_path = android_dir.join(APP_GRADLE_PATH);
+    let gradle = fs::read_to_string(&gradle_path)
+        .with_context(|| format!("Failed to read: {}", gradle_path.display()))?;
+
+    let mut findings = Vec::new();
+    check_exported(&manifest, &gradle, &mut findings);
+    check_packages(&manifest, &gradle, &mut findings);
+    check_com_example(android_dir, &mut findings)?;
+    check_release_signing(&gradle, &mut findings);
+
+    let findings: Vec<_> = findings
+        .into_iter()
+        .filter(|finding| !config.ignore.iter().any(|rule| rule == finding.rule))
+        .collect();
+    if findings.is_empty() {
+        log!("✓ Android lint passed");
+        return Ok(());
+    }
+
+    for finding in &findings {
+        let message = format!("[{}] {}", finding.rule, finding.message);
+        if config.warn_only {
+            report::warn(message);
+        } else {
+            log!("✗ {message}");
+        }
+    }
+    if !config.warn_only {
+        bail!(
+            "Android lint found {} issue(s); fix them, list the rule in android.lint.ignore, or set android.lint.warn_only",
+            findings.len()
+        );
+    }
+    Ok(())
+}
+
+/// `targetSdk` from the app script; Flutter's own default is treated as 31+.
+fn target_sdk(gradle: &str) -> Option<u32> {
+    gradle.lines().find_map(|line| {
+        let rest = line.trim().strip_prefix("targetSdk")?;
+        let value = rest.trim_start().strip_prefix('=')?.trim();
+        value.parse().ok()
+    })
+}
+
+fn check_exported(manifest: &str, gradle: &str, findings: &mut Vec<Finding>) {
+    if target_sdk(gradle).is_some_and(|sdk| sdk < 31) {
+        return;
+    }
+    for component in COMPONENTS {
+        for span in find_elements(manifest, component) {
+            let start_tag = &manifest[span.start_tag.clone()];
+            if !has_intent_filter(&manifest[span.body])
+                || attribute_value(start_tag, "android:exported").is_some()
+            {
+                continue;
+            }
+            findings.push(Finding {
+                rule: "missing-exported",
+                message: format!(
+                    "<{} android:name=\"{}\"> has intent filters but no android:exported (required on API 31+)",
+                    component,
+                    attribute_value(start_tag, "android:name").unwrap_or("?")
+                ),
+            });
+        }
+    }
+}
+
+/// Whether a component body declares an `<intent-filter>` outside comments.
+fn has_intent_filter(body: &str) -> bool {
+    let mut rest = body;
+    loop {
+        let (code, comment) = rest.split_once("<!--").unwrap_or((rest, ""));
+        if code.contains("<intent-filter") {
+            return true;
+        }
+        match comment.split_once("-->") {
+            Some((_, after)) => rest = after,
+            None => return false,
+        }
+    }
+}
+
+fn check_packages(manifest: &str, gradle: &str, findings: &mut Vec<Finding>) {
+    let namespace = string_value(gradle, "namespace");
+    let application_id = string_value(gradle, "applicationId");
+    for (key, value) in [
+        ("namespace", &namespace),
+        ("applicationId", &application_id),
+    ] {
+        if let Some(value) = value.as_deref().filter(|v| !is_valid_java_package(v)) {
+            findings.push(Finding {
+                rule: "invalid-package",
+                message: format!("{key} \"{value}\" is not a valid Java package name"),
+            });
+        }
+    }
+
+    let package = find_start_tag(manifest, "manifest")
+        .and_then(|range| attribute_value(&manifest[range], "package"));
+    let mismatch = match (package, namespace.as_deref()) {
+        (Some(package), Some(namespace)) if package != namespace => Some((package, namespace)),
+        _ => None,
+    };
+    if let Some((package, namespace)) = mismatch {
+        findings.push(Finding {
+            rule: "namespace-mismatch",
+            message: format!(
+                "AndroidManifest.xml package \"{package}\" differs from Gradle namespace \"{namespace}\""
+            ),
+        });
+    }
+}
+
+fn is_valid_java_package(value: &str) -> bool {
+    let segments: Vec<_> = value.split('.').collect();
+    segments.len() >= 2
+        && segments.iter().all(|segment| {
+            let mut chars = segment.chars();
+            chars
+                .next()
+                .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && !JAVA_KEYWORDS
+                    .split_whitespace()
+                    .any(|word| word == *segment)
+        })
+}
+
+fn check_com_example(android_dir: &Path, findings: &mut Vec<Finding>) -> Result<()> {
+    let walker = WalkDir::new(android_dir).into_iter().filter_entry(|entry| {
+        !(entry.file_type().is_dir()
+            && SKIP_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()))
+    });
+    for entry in walker {
+        let entry = entry?;
+        let scanned = entry
+            .path()
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| SCANNED_EXTENSIONS.contains(&ext));
+        if !entry.file_type().is_file() || !scanned {
+            continue;
+        }
+        let Ok(content) = fs::read_to_string(entry.path()) else {
+            continue;
+        };
+        if let Some(line) = content
+            .lines()
+            .position(|line| line.contains("com.example"))
+        {
+            findings.push(Finding {
+                rule: "com-example",
+                message: format!(
+                    "{}:{} still references com.example",
+                    entry.path().strip_prefix(android_dir)?.display(),
+                    line + 1
+                ),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn check_release_signing(gradle: &str, findings: &mut Vec<Finding>) {
+    let release = find_block(gradle, "android", 0..gradle.len())
+        .and_then(|android| find_block(gradle, "buildTypes", android))
+        .and_then(|build_types| find_build_type(gradle, build_types, "release"));
+    if let Some(body) = release {
+        let uses_debug = gradle[body].lines().any(|line| {
+            let line = line.trim();
+            !line.starts_with("//")
+                && line.starts_with("signingConfig")
+                && (line.contains("getByName(\"debug\")") || line.ends_with("signingConfigs.debug"))
+        });
+        if uses_debug {
+            findings.push(Finding {
+                rule: "release-debug-keystore",
+                message: format!("{APP_GRADLE_PATH}: the release build type is signed with the debug signing config"),
+            });
+        }
+    }
+    if gradle.lines().any(|line| {
+        let line = line.trim();
+        line.starts_with("storeFile") && line.contains("debug.keystore")
+    }) {
+        findings.push(Finding {
+            rule: "release-debug-keystore",
+            message: format!("{APP_GRADLE_PATH}: a signing config points at debug.keystore"),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(check: impl FnOnce(&mut Vec<Finding>)) -> Vec<&'static str> {
+        let mut findings = Vec::new();
+        check(&mut findings);
+        findings.iter().map(|finding| finding.rule).collect()
+    }
+
+    const MANIFEST: &str = r#"<manifest xmlns:android="http://schemas.android.com/apk/res/android">
+    <application>
+        <activity android:name=".MainActivity" android:exported="true">
+            <intent-filter><action android:name="android.intent.action.MAIN"/></intent-filter>
+        </activity>
+        <activity android:name=".Share">
+            <intent-filter><action android:name="android.intent.action.SEND"/></intent-filter>
+        </activity>
+        <service android:name=".Sync"/>
+        <receiver android:name=".Boot">
+            <!-- <intent-filter> -->
+        </receiver>
+    </application>
+</manifest>"#;
+
+    #[test]
+    fn check_exported_requires_attribute_with_intent_filters() {
+        let mut findings = Vec::new();
+        check_exported(MANIFEST, "android {\n}\n", &mut findings);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].rule, "missing-exported");
+        assert!(
+            findings[0].message.contains(".Share"),
+            "{}",
+            findings[0].message
+        );
+    }
+
+    #[test]
+    fn check_exported_skips_old_target_sdk() {
+        let gradle = "defaultConfig {\n    targetSdk = 30\n}\n";
+        assert!(rules(|findings| check_exported(MANIFEST, gradle, findings)).is_empty());
+        let gradle = "defaultConfig {\n    targetSdk = 34\n}\n";
+        assert_eq!(
+            rules(|findings| check_exported(MANIFEST, gradle, findings)),
+            ["missing-exported"]
+        );
+        assert_eq!(target_sdk("targetSdk = flutter.targetSdkVersion"), None);
+    }
+
+    #[test]
+    fn is_valid_java_package_names() {
+        assert!(is_valid_java_package("com.acme.app"));
+        assert!(is_valid_java_package("com.acme_1._app"));
+        assert!(!is_valid_java_package("app"));
+        assert!(!is_valid_java_package("com.acme."));
+        assert!(!is_valid_java_package("com.1acme"));
+        assert!(!is_valid_java_package("com.acme-app"));
+        assert!(!is_valid_java_package("com.new.app"));
+        assert!(!is_valid_java_package("com.acme.int"));
+    }
+
+    #[test]
+    fn check_packages_namespace() {
+        let gradle = "android {\n    namespace = \"com.acme.app\"\n    defaultConfig {\n        applicationId = \"com.acme.class\"\n    }\n}\n";
+        let manifest = "<manifest package=\"com.acme.other\">\n</manifest>";
+        assert_eq!(
+            rules(|findings| check_packages(manifest, gradle, findings)),
+            ["invalid-package", "namespace-mismatch"]
+        );
+        let manifest = "<manifest package=\"com.acme.app\">\n</manifest>";
+        let gradle = gradle.replace("com.acme.class", "com.acme.app");
+        assert!(rules(|findings| check_packages(manifest, &gradle, findings)).is_empty());
+    }
+
+    #[test]
+    fn check_release_signing_debug() {
+        let gradle = "android {\n    buildTypes {\n        release {\n            signingConfig = signingConfigs.getByName(\"debug\")\n        }\n    }\n}\n";
+        assert_eq!(
+            rules(|findings| check_release_signing(gradle, findings)),
+            ["release-debug-keystore"]
+        );
+        let gradle = gradle.replace("signingConfig =", "// signingConfig =");
+        assert!(rules(|findings| check_release_signing(&gradle, findings)).is_empty());
+    }
+}
@@ -1,15 +1,44 @@
+/// `println!` for progress output; it goes to stderr while the report is
+/// printed to stdout.
+macro_rules! log {
+    ($($arg:tt)*) => {
+        if $crate::report::progress_on_stderr() {
+            eprintln!($($arg)*)
+        } else {
+            println!($($arg)*)
+        }
+    };
+}
+
 mod android;
+mod brand;
 mod config;
+mod deep_links;
+mod firebase;
+mod flutter_sdk;
+mod gradle;
+mod hooks;
+mod init;
+mod lint;
+mod manifest;
+mod patches;
+mod preserve;
+mod proguard;
+mod report;
 mod utils;
+mod version;
 mod web;
 mod windows;
 
 use anyhow::{bail, Result};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use std::path::{Path, PathBuf};
 
-use config::{build_template_vars, expand_config, load_config};
-use utils::{remove_dir_all_with_retry, resolve_cmd, run_flutter_create};
+use config::{build_template_vars, expand_config, load_config, BrandConfig, Config};
+use flutter_sdk::resolve_flutter_cmd;
+use report::{FileAction, ReportFormat};
+use utils::{platforms_root, remove_dir_all_with_retry, run_flutter_create};
+use version::AppVersion;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -17,68 +46,252 @@ use utils::{remove_dir_all_with_retry, resolve_cmd, run_flutter_create};
     about = "Generate Flutter platform directories"
 )]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Commands>,
+
     #[arg(long, value_name = "FILE", default_value = "app.pkl")]
     config: PathBuf,
 
-    #[arg(long, value_name = "CMD", default_value = "flutter")]
+    #[arg(long, value_name = "CMD", default_value = "flutter", global = true)]
     flutter_cmd: String,
 
-    #[arg(long, value_name = "DIR", default_value = ".")]
+    #[arg(long, value_name = "DIR", default_value = ".", global = true)]
     project_dir: Option<PathBuf>,
 
     #[arg(long, help = "Preview changes without writing files")]
     dry_run: bool,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Firebase profile to install (defaults to firebase.default_profile; with --all-brands, a brand's own profile wins)"
+    )]
+    firebase_profile: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        help = "Generate the named brand from config"
+    )]
+    brand: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "brand",
+        help = "Generate every brand into its own output directory"
+    )]
+    all_brands: bool,
+
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        help = "Emit a machine-readable summary of the run (progress moves to stderr when it goes to stdout)"
+    )]
+    report: Option<ReportFormat>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        requires = "report",
+        help = "Write the report to FILE instead of stdout"
+    )]
+    report_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        help = "Warn instead of failing when Flutter doesn't match the flutter config section"
+    )]
+    allow_flutter_mismatch: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Write a starter config (and android overlay) from an existing project
+    Init(init::InitArgs),
 }
 
 fn main() -> Result<()> {
     let args = Args::parse();
-    let config_path = args.config;
-    let flutter_cmd = args.flutter_cmd;
-    let project_dir = args.project_dir;
-    let dry_run = args.dry_run;
 
-    if dry_run {
-        println!("[DRY RUN] Preview mode - no files will be modified\n");
+    if let Some(Commands::Init(init_args)) = &args.command {
+        let project_dir = args.project_dir.unwrap_or_else(|| PathBuf::from("."));
+        let flutter_cmd = resolve_flutter_cmd(&args.flutter_cmd, &project_dir)?;
+        return init::run_init(&project_dir, &flutter_cmd, init_args);
+    }
+
+    if args.report.is_some() && args.report_file.is_none() {
+        // Keep stdout parseable: it only carries the report.
+        report::progress_to_stderr();
+    }
+    let result = run(&args);
+    if let Some(format) = args.report {
+        report::write(format, args.report_file.as_deref(), result.as_ref().err())?;
+    }
+    result
+}
+
+/// Generate the project, or every brand with `--all-brands`.
+fn run(args: &Args) -> Result<()> {
+    if args.dry_run {
+        log!("[DRY RUN] Preview mode - no files will be modified\n");
     }
 
-    let mut cfg = load_config(&config_path)?;
+    let mut cfg = load_config(&args.config)?;
+    let brands = std::mem::take(&mut cfg.brands);
 
-    let project_dir = project_dir.unwrap_or_else(|| {
-        config_path
+    let project_dir = args.project_dir.clone().unwrap_or_else(|| {
+        args.config
             .parent()
             .unwrap_or_else(|| Path::new("."))
             .to_path_buf()
     });
 
+    if args.all_brands {
+        if brands.is_empty() {
+            bail!("--all-brands requires a brands list in config");
+        }
+        let output_dirs: Vec<_> = brands
+            .iter()
+            .map(|brand| brand::output_dir(&project_dir, brand))
+            .collect();
+        for (brand, output_dir) in brands.iter().zip(&output_dirs) {
+            log!("\n=== Brand {} → {} ===", brand.name, output_dir.display());
+            let mut brand_cfg = cfg.clone();
+            brand_cfg.apply_brand(brand);
+            if args.dry_run {
+                // The copy doesn't exist yet, so plan against the project it is made from.
+                log!("[DRY RUN] Would copy project to: {}", output_dir.display());
+                generate(brand_cfg, &project_dir, args, Some(brand))?;
+            } else {
+                brand::copy_project(&project_dir, output_dir, &output_dirs)?;
+                generate(brand_cfg, output_dir, args, Some(brand))?;
+            }
+        }
+        return Ok(());
+    }
+
+    let brand = match args.brand.as_deref() {
+        Some(name) => Some(brand::find_brand(&brands, name)?),
+        None => None,
+    };
+    if let Some(brand) = brand {
+        cfg.apply_brand(brand);
+    }
+    generate(cfg, &project_dir, args, brand)
+}
+
+/// Regenerate the platform directories of `project_dir` from `cfg`.
+fn generate(
+    mut cfg: Config,
+    project_dir: &Path,
+    args: &Args,
+    brand: Option<&BrandConfig>,
+) -> Result<()> {
+    let project_dir = project_dir.to_path_buf();
+    let flutter_cmd = &args.flutter_cmd;
+    let dry_run = args.dry_run;
+    // Under --all-brands, --firebase-profile only covers brands without one.
+    let brand_profile = brand
+        .filter(|_| args.all_brands)
+        .and_then(|brand| brand.firebase_profile.as_deref());
+    let firebase_profile = brand_profile.or(args.firebase_profile.as_deref());
+    report::begin(
+        &project_dir,
+        brand.map(|brand| brand.name.as_str()),
+        dry_run,
+    );
+
     // Use version from app.pkl's pubspec config
     if cfg.version.is_none() {
-        if let Some(pubspec_config) = &cfg.pubspec {
-            cfg.version = pubspec_config.version.clone();
-            if let Some(version) = &cfg.version {
-                println!("Using version from config: {}", version);
-            }
+        cfg.version = cfg
+            .pubspec
+            .as_ref()
+            .and_then(|pubspec| pubspec.version.clone());
+        if let Some(version) = &cfg.version {
+            log!("Using version from config: {}", version);
         }
     }
 
     expand_config(&mut cfg)?;
+    let version = cfg.version.as_deref().map(AppVersion::parse).transpose()?;
+
+    // Validate Firebase files up front so a mismatch doesn't leave the
+    // platform directories half-regenerated.
+    let firebase_files = match &cfg.firebase {
+        Some(firebase) => Some(firebase::resolve_firebase_files(
+            &project_dir,
+            firebase,
+            firebase_profile,
+            cfg.android
+                .template_vars
+                .application_id
+                .as_deref()
+                .unwrap_or_default(),
+        )?),
+        None => None,
+    };
 
     // Determine which platforms to process based on config
-    let platforms = cfg
-        .create
-        .platforms
-        .as_ref()
-        .map(|p| p.as_slice())
-        .unwrap_or(&[]);
-    let process_android = platforms.is_empty() || platforms.contains(&"android".to_string());
-    let process_web = platforms.contains(&"web".to_string());
-    let process_windows = platforms.contains(&"windows".to_string());
+    let platforms = cfg.create.platforms.as_deref().unwrap_or(&[]);
+    // Plugin templates generate their app in example/; module and package
+    // templates have no platform directories to process.
+    let app_dir = cfg.create.app_dir(&project_dir);
+    let has_app = app_dir.is_some();
+    let app_dir = app_dir.unwrap_or_else(|| project_dir.clone());
+    let process_android =
+        has_app && (platforms.is_empty() || platforms.contains(&"android".to_string()));
+    let process_web = has_app && platforms.contains(&"web".to_string());
+    let process_windows = has_app && platforms.contains(&"windows".to_string());
+
+    let template_vars = build_template_vars(&cfg);
+    let processed: Vec<_> = [
+        ("android", process_android),
+        ("web", process_web),
+        ("windows", process_windows),
+    ]
+    .into_iter()
+    .filter_map(|(platform, enabled)| enabled.then_some(platform))
+    .collect();
+    report::inputs(&processed, &template_vars);
+    let hook_env = hooks::hook_env(&project_dir, &app_dir, &template_vars);
+    let run_hooks = |stage: &str, commands: &[String]| {
+        hooks::run_hooks(stage, commands, &project_dir, &hook_env)
+    };
+
+    // Check the SDK before anything is removed
+    let flutter_cmd = resolve_flutter_cmd(flutter_cmd, &project_dir)?;
+    if let Some(flutter) = &cfg.flutter {
+        flutter_sdk::check_flutter_version(&flutter_cmd, flutter, args.allow_flutter_mismatch)?;
+    }
+
+    // Stash preserved local files before the platform directories are removed
+    let mut stashes = Vec::new();
+    for (platform, enabled, patterns) in [
+        ("android", process_android, &cfg.preserve.android),
+        ("web", process_web, &cfg.preserve.web),
+        ("windows", process_windows, &cfg.preserve.windows),
+    ] {
+        if !enabled {
+            continue;
+        }
+        if dry_run {
+            for rel in preserve::matching_files(&app_dir.join(platform), patterns)? {
+                log!("[DRY RUN] Would preserve: {platform}/{}", rel.display());
+            }
+        } else if let Some(stash) =
+            preserve::stash_files(&project_dir, &app_dir, platform, patterns)?
+        {
+            stashes.push(stash);
+        }
+    }
 
     // Remove existing platform directories
     if process_android {
-        let android_dir = project_dir.join("android");
+        let android_dir = app_dir.join("android");
         if android_dir.exists() {
             if dry_run {
-                println!(
+                log!(
                     "[DRY RUN] Would remove directory: {}",
                     android_dir.display()
                 );
@@ -89,10 +302,10 @@ fn main() -> Result<()> {
     }
 
     if process_web {
-        let web_dir = project_dir.join("web");
+        let web_dir = app_dir.join("web");
         if web_dir.exists() {
             if dry_run {
-                println!("[DRY RUN] Would remove directory: {}", web_dir.display());
+                log!("[DRY RUN] Would remove directory: {}", web_dir.display());
             } else {
                 remove_dir_all_with_retry(&web_dir)?;
             }
@@ -100,10 +313,10 @@ fn main() -> Result<()> {
     }
 
     if process_windows {
-        let windows_dir = project_dir.join("windows");
+        let windows_dir = app_dir.join("windows");
         if windows_dir.exists() {
             if dry_run {
-                println!(
+                log!(
                     "[DRY RUN] Would remove directory: {}",
                     windows_dir.display()
                 );
@@ -113,8 +326,8 @@ fn main() -> Result<()> {
         }
     }
 
-    let flutter_cmd = resolve_cmd(&flutter_cmd)?;
     if !dry_run {
+        run_hooks("before_create", &cfg.hooks.before_create)?;
         run_flutter_create(
             &project_dir,
             &flutter_cmd,
@@ -123,66 +336,195 @@ fn main() -> Result<()> {
             cfg.description.as_deref(),
             &cfg.create,
         )?;
+        for platform in &processed {
+            report::files_under(FileAction::Created, &app_dir.join(platform))?;
+        }
+        run_hooks("after_create", &cfg.hooks.after_create)?;
     } else {
-        println!("[DRY RUN] Would run flutter create with:");
-        println!("  project_name: {}", cfg.project_name);
+        log!("[DRY RUN] Would run flutter create with:");
+        log!("  project_name: {}", cfg.project_name);
         if let Some(org) = &cfg.org {
-            println!("  org: {}", org);
+            log!("  org: {}", org);
         }
         if let Some(desc) = &cfg.description {
-            println!("  description: {}", desc);
+            log!("  description: {}", desc);
+        }
+        if let Some(brand) = brand {
+            log!("  brand: {}", brand.name);
+        }
+        log!("  template: {}", cfg.create.template());
+        if let Some(version) = &version {
+            log!("  version: {version}");
+        }
+        log!("  platforms: {:?}", cfg.create.platforms);
+        if let Some(platforms) = &cfg.create.plugin_platforms {
+            log!("  plugin_platforms: {:?}", platforms);
+        }
+        if let Some(sample) = &cfg.create.sample {
+            log!("  sample: {}", sample);
+        }
+        if let Some(files) = &firebase_files {
+            log!("  firebase_profile: {}", files.profile);
         }
-        println!("  platforms: {:?}", cfg.create.platforms);
-        println!("  android_language: {:?}\n", cfg.create.android_language);
+        for patch in &cfg.patches {
+            log!("  patch: {} {}", patch.op.name(), patch.file);
+        }
+        for (stage, commands) in hooks::stages(&cfg.hooks) {
+            for command in commands {
+                log!("  hook {stage}: {command}");
+            }
+        }
+        log!("  android_language: {:?}", cfg.create.android_language);
+        log!("  ios_language: {:?}\n", cfg.create.ios_language);
         return Ok(());
     }
 
+    // `--all-brands` generates into a throwaway copy that keeps the brand's
+    // assets; in place they are only swapped in for the generation.
+    let assets_backup = match brand.and_then(|brand| brand.assets_dir.as_deref()) {
+        Some(assets_dir) => brand::copy_assets(&project_dir, assets_dir, !args.all_brands)?,
+        None => None,
+    };
+    let label = brand.and_then(|brand| brand.label.as_deref());
+
     // Process Android platform
     if process_android {
-        let android_dir = project_dir.join("android");
+        let android_dir = app_dir.join("android");
         if !android_dir.exists() {
             bail!(
                 "Generated android directory not found at: {}",
                 android_dir.display()
             );
         }
-        let template_vars = build_template_vars(&cfg);
+        run_hooks("before_android", &cfg.hooks.android.before)?;
         android::process_android_platform(
             &project_dir,
+            &app_dir,
             &cfg.android,
             cfg.platforms_dir.as_deref(),
             &template_vars,
         )?;
+        if let Some(deep_links) = &cfg.deep_links {
+            deep_links::apply_android_intent_filters(&android_dir, deep_links)?;
+        }
+        if let Some(files) = &firebase_files {
+            firebase::copy_android_files(&app_dir, files)?;
+        }
+        if let Some(version) = &version {
+            version::apply_android_version(&android_dir, version)?;
+        }
+        lint::lint_android_project(&android_dir, &cfg.android.lint)?;
+        run_hooks("after_android", &cfg.hooks.android.after)?;
     }
 
     // Process Web platform
     if process_web {
-        let web_dir = project_dir.join("web");
+        let web_dir = app_dir.join("web");
         if !web_dir.exists() {
             bail!(
                 "Generated web directory not found at: {}",
                 web_dir.display()
             );
         }
-        web::process_web_platform(&project_dir)?;
+        run_hooks("before_web", &cfg.hooks.web.before)?;
+        web::process_web_platform(&app_dir)?;
+        if let Some(label) = label {
+            brand::apply_web_label(&web_dir, label)?;
+        }
+        if let Some(version) = &version {
+            version::apply_web_version(&web_dir, version)?;
+        }
+        run_hooks("after_web", &cfg.hooks.web.after)?;
     }
 
     // Process Windows platform
     if process_windows {
-        let windows_dir = project_dir.join("windows");
+        let windows_dir = app_dir.join("windows");
         if !windows_dir.exists() {
             bail!(
                 "Generated windows directory not found at: {}",
                 windows_dir.display()
             );
         }
+        run_hooks("before_windows", &cfg.hooks.windows.before)?;
         if let Some(windows_config) = &cfg.windows {
-            windows::process_windows_platform(&project_dir, windows_config)?;
+            windows::process_windows_platform(&app_dir, windows_config)?;
         } else {
-            windows::process_windows_platform(&project_dir, &Default::default())?;
+            windows::process_windows_platform(&app_dir, &Default::default())?;
+        }
+        if let Some(version) = &version {
+            version::apply_windows_version(&windows_dir, version)?;
         }
+        if let Some(label) = label {
+            brand::apply_windows_label(&windows_dir, label)?;
+        }
+        run_hooks("after_windows", &cfg.hooks.windows.after)?;
+    }
+
+    // Restore preserved files over the regenerated output
+    if !stashes.is_empty() {
+        let mut writers = Vec::new();
+        if process_android {
+            let android_dir = app_dir.join("android");
+            for rel in android::overlay_files(&project_dir, cfg.platforms_dir.as_deref())? {
+                writers.push((android_dir.join(rel), "the android overlay".to_string()));
+            }
+            if let Some(files) = firebase_files
+                .as_ref()
+                .filter(|files| files.google_services_json.is_some())
+            {
+                writers.push((
+                    android_dir.join("app").join("google-services.json"),
+                    format!("firebase profile '{}'", files.profile),
+                ));
+            }
+        }
+        for (index, patch) in cfg.patches.iter().enumerate() {
+            writers.push((
+                project_dir.join(&patch.file),
+                format!("patch #{}", index + 1),
+            ));
+        }
+        for stash in stashes {
+            preserve::report_conflicts(&stash, &writers);
+            stash.restore()?;
+        }
+    }
+
+    if let Some(files) = &firebase_files {
+        firebase::copy_ios_files(&app_dir, files)?;
+    }
+    if let Some(version) = version.as_ref().filter(|_| has_app) {
+        version::apply_ios_version(&app_dir, version)?;
+    }
+    if has_app && !cfg.android.strings.is_empty() {
+        android::write_ios_display_names(&app_dir, &cfg.android.strings)?;
+    }
+
+    if let Some(deep_links) = &cfg.deep_links {
+        let tv = &cfg.android.template_vars;
+        let application_id = tv.application_id.as_deref().unwrap_or_default();
+        let keystore = deep_links::resolve_keystore_path(
+            &project_dir,
+            &app_dir,
+            platforms_root(cfg.platforms_dir.as_deref()),
+            tv.store_file.as_deref(),
+        );
+        let key_alias = tv.key_alias.as_deref();
+        deep_links::write_well_known_files(
+            &project_dir,
+            deep_links,
+            application_id,
+            key_alias.map(|alias| (keystore.as_path(), alias)),
+        )?;
+    }
+
+    patches::apply_patches(&project_dir, &cfg.patches)?;
+    run_hooks("after_generate", &cfg.hooks.after_generate)?;
+    if let Some(backup) = assets_backup {
+        backup.restore()?;
     }
 
-    println!("Platform directories generated successfully!");
+    log!("Platform directories generated successfully!");
     Ok(())
 }
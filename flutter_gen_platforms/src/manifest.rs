@@ -0,0 +1,167 @@
+use std::ops::Range;
+
+/// Path of the main manifest, relative to android/.
+pub const MANIFEST_PATH: &str = "app/src/main/AndroidManifest.xml";
+
+/// Find the byte range of the first start tag named `tag`, from `<` up to and
+/// including the closing `>`. Quoted attribute values may contain `>`.
+pub fn find_start_tag(content: &str, tag: &str) -> Option<Range<usize>> {
+    find_start_tag_from(content, tag, 0)
+}
+
+/// Like [`find_start_tag`], but starts searching at byte offset `from`.
+pub fn find_start_tag_from(content: &str, tag: &str, from: usize) -> Option<Range<usize>> {
+    let needle = format!("<{tag}");
+    let mut search = from;
+    while let Some(pos) = content[search..].find(&needle) {
+        let start = search + pos;
+        let after = start + needle.len();
+        let next = content[after..].chars().next();
+        if !matches!(next, Some(c) if c.is_whitespace() || c == '>' || c == '/') {
+            search = after;
+            continue;
+        }
+        let end = find_tag_end(content, after)?;
+        return Some(start..end);
+    }
+    None
+}
+
+/// Return the index just past the `>` that closes the tag starting before `from`.
+fn find_tag_end(content: &str, from: usize) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    for (offset, c) in content[from..].char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '>' => return Some(from + offset + 1),
+            None => {}
+        }
+    }
+    None
+}
+
+/// Read the value of attribute `name` (e.g. `android:label`) from a start tag.
+pub fn attribute_value<'a>(tag: &'a str, name: &str) -> Option<&'a str> {
+    let range = attribute_value_range(tag, name)?;
+    Some(&tag[range])
+}
+
+/// Byte range of the quoted value of attribute `name` within a start tag.
+fn attribute_value_range(tag: &str, name: &str) -> Option<Range<usize>> {
+    let mut search = 0;
+    while let Some(pos) = tag[search..].find(name) {
+        let start = search + pos;
+        search = start + name.len();
+        let preceded_by_space = tag[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_whitespace());
+        if !preceded_by_space {
+            continue;
+        }
+        let Some(after_eq) = tag[search..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let after_eq = after_eq.trim_start();
+        let Some(quote) = after_eq.chars().next().filter(|c| *c == '"' || *c == '\'') else {
+            continue;
+        };
+        let value_start = tag.len() - after_eq.len() + 1;
+        let value_len = tag[value_start..].find(quote)?;
+        return Some(value_start..value_start + value_len);
+    }
+    None
+}
+
+/// Set attribute `name` on a start tag, replacing an existing value or
+/// appending the attribute before the closing `>`/`/>`.
+pub fn set_attribute(tag: &str, name: &str, value: &str) -> String {
+    if let Some(range) = attribute_value_range(tag, name) {
+        return format!("{}{}{}", &tag[..range.start], value, &tag[range.end..]);
+    }
+    let close = if tag.ends_with("/>") {
+        tag.len() - 2
+    } else {
+        tag.len() - 1
+    };
+    let head = tag[..close].trim_end();
+    // Multi-line tags get the new attribute on its own line, aligned with the
+    // last existing attribute.
+    let separator = match head.rsplit_once('\n') {
+        Some((_, last_line)) => {
+            let indent: String = last_line
+                .chars()
+                .take_while(|c| c.is_whitespace())
+                .collect();
+            format!("\n{indent}")
+        }
+        None => " ".to_string(),
+    };
+    format!("{head}{separator}{name}=\"{value}\"{}", &tag[close..])
+}
+
+/// Replace the first start tag named `tag` in `content` using `edit`.
+/// Returns `None` if the tag does not exist.
+pub fn edit_start_tag(
+    content: &str,
+    tag: &str,
+    edit: impl FnOnce(&str) -> String,
+) -> Option<String> {
+    let range = find_start_tag(content, tag)?;
+    let edited = edit(&content[range.clone()]);
+    Some(format!(
+        "{}{}{}",
+        &content[..range.start],
+        edited,
+        &content[range.end..]
+    ))
+}
+
+/// Location of an element's start tag and body, the latter up to (not
+/// including) the matching end tag. Self-closing elements have an empty body.
+pub struct ElementSpan {
+    pub start_tag: Range<usize>,
+    pub body: Range<usize>,
+}
+
+/// Find all non-nested elements named `tag`.
+pub fn find_elements(content: &str, tag: &str) -> Vec<ElementSpan> {
+    let close = format!("</{tag}>");
+    let mut spans = Vec::new();
+    let mut search = 0;
+    while let Some(start_tag) = find_start_tag_from(content, tag, search) {
+        let body_start = start_tag.end;
+        let body_end = if content[start_tag.clone()].ends_with("/>") {
+            body_start
+        } else {
+            match content[body_start..].find(&close) {
+                Some(pos) => body_start + pos,
+                None => break,
+            }
+        };
+        search = body_end;
+        spans.push(ElementSpan {
+            start_tag,
+            body: body_start..body_end,
+        });
+    }
+    spans
+}
+
+/// Find the launcher activity (the one handling `android.intent.action.MAIN`).
+pub fn find_main_activity(content: &str) -> Option<ElementSpan> {
+    find_elements(content, "activity")
+        .into_iter()
+        .find(|span| content[span.body.clone()].contains("android.intent.action.MAIN"))
+}
+
+/// Escape a value for use inside a double-quoted XML attribute.
+pub fn escape_attr(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
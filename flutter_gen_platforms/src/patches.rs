@@ -0,0 +1,434 @@
+use anyhow::{bail, Context, Result};
+use regex::Regex;
+use serde_json::Value;
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use xmltree::{Element, XMLNode};
+
+use crate::android::set_properties_value;
+use crate::config::{PatchConfig, PatchOp};
+use crate::manifest::{escape_attr, find_start_tag_from, set_attribute};
+use crate::report::{self, FileAction};
+
+/// Apply every configured patch, in order, to files under `project_dir`.
+/// A patch whose pattern, anchor or path doesn't match is an error.
+pub fn apply_patches(project_dir: &Path, patches: &[PatchConfig]) -> Result<()> {
+    for (index, patch) in patches.iter().enumerate() {
+        let path = project_dir.join(&patch.file);
+        apply_patch(&path, &patch.op).with_context(|| {
+            format!(
+                "Patch #{} ({}) failed on {}",
+                index + 1,
+                patch.op.name(),
+                path.display()
+            )
+        })?;
+        report::file(FileAction::Patched, &path);
+    }
+    if !patches.is_empty() {
+        log!("✓ Applied {} patch(es)", patches.len());
+    }
+    Ok(())
+}
+
+fn apply_patch(path: &Path, op: &PatchOp) -> Result<()> {
+    if !path.exists() {
+        bail!("File not found");
+    }
+    let content = read(path)?;
+    let updated = match op {
+        PatchOp::RegexReplace {
+            pattern,
+            replacement,
+            all,
+        } => {
+            let re = Regex::new(pattern).with_context(|| format!("Invalid regex: {pattern}"))?;
+            if !re.is_match(&content) {
+                bail!("Pattern not found: {pattern}");
+            }
+            if *all {
+                re.replace_all(&content, replacement.as_str()).into_owned()
+            } else {
+                re.replace(&content, replacement.as_str()).into_owned()
+            }
+        }
+        PatchOp::InsertAfter { anchor, line } => insert_after(&content, anchor, line)?,
+        PatchOp::XmlSet {
+            path: xml_path,
+            attribute,
+            value,
+        } => xml_set(&content, xml_path, attribute.as_deref(), value)?,
+        PatchOp::JsonSet { pointer, value } => json_set(&content, pointer, value)?,
+        PatchOp::PropertiesSet { key, value } => set_properties_value(&content, key, value),
+    };
+    write(path, &updated)
+}
+
+fn read(path: &Path) -> Result<String> {
+    fs::read_to_string(path).with_context(|| format!("Failed to read: {}", path.display()))
+}
+
+fn write(path: &Path, content: &str) -> Result<()> {
+    fs::write(path, content).with_context(|| format!("Failed to write: {}", path.display()))
+}
+
+/// Insert `line` after the first line containing `anchor`.
+fn insert_after(content: &str, anchor: &str, line: &str) -> Result<String> {
+    let mut out = String::with_capacity(content.len() + line.len() + 1);
+    let mut inserted = false;
+    for current in content.split_inclusive('\n') {
+        out.push_str(current);
+        if !inserted && current.contains(anchor) {
+            let ending = if current.ends_with("\r\n") {
+                "\r\n"
+            } else {
+                "\n"
+            };
+            if !current.ends_with('\n') {
+                out.push_str(ending);
+            }
+            out.push_str(line);
+            out.push_str(ending);
+            inserted = true;
+        }
+    }
+    if !inserted {
+        bail!("Anchor not found: {anchor}");
+    }
+    Ok(out)
+}
+
+/// Set the value at a JSON pointer. The parent must exist; the final key may
+/// be new when the parent is an object.
+fn json_set(content: &str, pointer: &str, value: &Value) -> Result<String> {
+    let mut doc: Value = serde_json::from_str(content).context("Failed to parse JSON")?;
+    if let Some(slot) = doc.pointer_mut(pointer) {
+        *slot = value.clone();
+    } else {
+        let (parent, key) = pointer
+            .rsplit_once('/')
+            .with_context(|| format!("Invalid JSON pointer: {pointer}"))?;
+        let key = key.replace("~1", "/").replace("~0", "~");
+        match doc.pointer_mut(parent) {
+            Some(Value::Object(map)) => {
+                map.insert(key, value.clone());
+            }
+            _ => bail!("JSON pointer not found: {pointer}"),
+        }
+    }
+    let mut out = serde_json::to_string_pretty(&doc)?;
+    out.push('\n');
+    Ok(out)
+}
+
+/// One step of an XML patch path: `name`, `name[@attr='value']` or `name[N]`
+/// (1-based, as in XPath).
+struct PathStep {
+    name: String,
+    attr: Option<(String, String)>,
+    position: Option<usize>,
+}
+
+fn parse_xml_path(path: &str) -> Result<Vec<PathStep>> {
+    path.trim_matches('/')
+        .split('/')
+        .map(|segment| {
+            let Some((name, predicate)) = segment.split_once('[') else {
+                return Ok(PathStep {
+                    name: segment.to_string(),
+                    attr: None,
+                    position: None,
+                });
+            };
+            let predicate = predicate
+                .strip_suffix(']')
+                .with_context(|| format!("Unclosed predicate in XML path segment: {segment}"))?;
+            let mut step = PathStep {
+                name: name.to_string(),
+                attr: None,
+                position: None,
+            };
+            if let Some(attr) = predicate.strip_prefix('@') {
+                let (key, value) = attr
+                    .split_once('=')
+                    .with_context(|| format!("Expected [@attr='value'] in: {segment}"))?;
+                let value = value.trim_matches(|c| c == '\'' || c == '"');
+                step.attr = Some((key.to_string(), value.to_string()));
+            } else {
+                let position: usize =
+                    predicate.parse().ok().filter(|n| *n > 0).with_context(|| {
+                        format!("Invalid position in XML path segment: {segment}")
+                    })?;
+                step.position = Some(position);
+            }
+            Ok(step)
+        })
+        .collect()
+}
+
+fn qualified_name(element: &Element) -> String {
+    match &element.prefix {
+        Some(prefix) => format!("{prefix}:{}", element.name),
+        None => element.name.clone(),
+    }
+}
+
+fn step_matches(step: &PathStep, element: &Element) -> bool {
+    if qualified_name(element) != step.name {
+        return false;
+    }
+    match &step.attr {
+        // xmltree keys attributes by local name, so `android:name` matches `name`.
+        Some((key, value)) => {
+            let local = key.rsplit(':').next().unwrap_or(key);
+            element.attributes.get(local) == Some(value)
+        }
+        None => true,
+    }
+}
+
+/// Resolve `steps` against the parsed document and return the target element.
+fn resolve_xml_path<'a>(root: &'a Element, steps: &[PathStep], path: &str) -> Result<&'a Element> {
+    let Some((first, rest)) = steps.split_first() else {
+        bail!("Empty XML path");
+    };
+    if !step_matches(first, root) {
+        bail!(
+            "XML path not found: {path} (root is <{}>)",
+            qualified_name(root)
+        );
+    }
+    let mut current = root;
+    for step in rest {
+        let mut candidates = current
+            .children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .filter(|child| step_matches(step, child));
+        let next = match step.position {
+            Some(position) => candidates.nth(position - 1),
+            None => candidates.next(),
+        };
+        current = next.with_context(|| format!("XML path not found: {path}"))?;
+    }
+    Ok(current)
+}
+
+/// Count elements with the same qualified name as `target` that precede it in
+/// document order.
+fn document_ordinal(root: &Element, target: &Element) -> usize {
+    fn walk(element: &Element, target: &Element, name: &str, count: &mut usize) -> bool {
+        if std::ptr::eq(element, target) {
+            return true;
+        }
+        if qualified_name(element) == name {
+            *count += 1;
+        }
+        element
+            .children
+            .iter()
+            .filter_map(XMLNode::as_element)
+            .any(|child| walk(child, target, name, count))
+    }
+    let mut count = 0;
+    walk(root, target, &qualified_name(target), &mut count);
+    count
+}
+
+/// Byte ranges of comments and CDATA sections, which may contain tag-like text.
+fn opaque_ranges(content: &str) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    for (open, close) in [("<!--", "-->"), ("<![CDATA[", "]]>")] {
+        let mut search = 0;
+        while let Some(pos) = content[search..].find(open) {
+            let start = search + pos;
+            let end = content[start..]
+                .find(close)
+                .map_or(content.len(), |p| start + p + close.len());
+            ranges.push(start..end);
+            search = end;
+        }
+    }
+    ranges
+}
+
+/// Find the `n`-th (0-based) start tag named `tag`, ignoring comments/CDATA.
+fn nth_start_tag(content: &str, tag: &str, n: usize) -> Option<Range<usize>> {
+    let opaque = opaque_ranges(content);
+    let mut search = 0;
+    let mut seen = 0;
+    while let Some(range) = find_start_tag_from(content, tag, search) {
+        search = range.end;
+        if opaque.iter().any(|r| r.contains(&range.start)) {
+            continue;
+        }
+        if seen == n {
+            return Some(range);
+        }
+        seen += 1;
+    }
+    None
+}
+
+/// Set an attribute (or the text content, when `attribute` is `None`) of the
+/// element at `path`. The element is located with xmltree and edited in place
+/// so prefixes, comments and formatting survive.
+fn xml_set(content: &str, path: &str, attribute: Option<&str>, value: &str) -> Result<String> {
+    let root = Element::parse(content.as_bytes()).context("Failed to parse XML")?;
+    let steps = parse_xml_path(path)?;
+    let target = resolve_xml_path(&root, &steps, path)?;
+    let name = qualified_name(target);
+    let tag_range = nth_start_tag(content, &name, document_ordinal(&root, target))
+        .with_context(|| format!("Could not locate <{name}> for XML path: {path}"))?;
+    let tag = &content[tag_range.clone()];
+
+    match attribute {
+        Some(attribute) => Ok(format!(
+            "{}{}{}",
+            &content[..tag_range.start],
+            set_attribute(tag, attribute, &escape_attr(value)),
+            &content[tag_range.end..]
+        )),
+        None => {
+            if target.children.iter().any(|c| c.as_element().is_some()) {
+                bail!("Cannot set text of <{name}>: it has child elements");
+            }
+            let text = value
+                .replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;");
+            if let Some(open) = tag.strip_suffix("/>") {
+                return Ok(format!(
+                    "{}{}>{text}</{name}>{}",
+                    &content[..tag_range.start],
+                    open.trim_end(),
+                    &content[tag_range.end..]
+                ));
+            }
+            let close = format!("</{name}>");
+            let body_end = content[tag_range.end..]
+                .find(&close)
+                .map(|p| tag_range.end + p)
+                .with_context(|| format!("Missing {close} for XML path: {path}"))?;
+            Ok(format!(
+                "{}{text}{}",
+                &content[..tag_range.end],
+                &content[body_end..]
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn insert_after_first_anchor() {
+        let content = "plugins {\n    id(\"a\")\n}\nplugins {\n}\n";
+        assert_eq!(
+            insert_after(content, "plugins {", "    id(\"b\")").unwrap(),
+            "plugins {\n    id(\"b\")\n    id(\"a\")\n}\nplugins {\n}\n"
+        );
+        assert_eq!(insert_after("a\r\nb", "b", "c").unwrap(), "a\r\nb\nc\n");
+        assert_eq!(
+            insert_after("a\r\nb\r\n", "a", "c").unwrap(),
+            "a\r\nc\r\nb\r\n"
+        );
+        assert!(insert_after("a\n", "missing", "c").is_err());
+    }
+
+    #[test]
+    fn json_set_pointer() {
+        let content = r#"{"name": "app", "nested": {"list": [1, 2]}}"#;
+        let updated = json_set(content, "/nested/list/1", &json!(5)).unwrap();
+        let value: Value = serde_json::from_str(&updated).unwrap();
+        assert_eq!(value, json!({"name": "app", "nested": {"list": [1, 5]}}));
+
+        let updated = json_set(content, "/nested/a~1b", &json!("x")).unwrap();
+        let value: Value = serde_json::from_str(&updated).unwrap();
+        assert_eq!(value["nested"]["a/b"], json!("x"));
+        assert!(updated.ends_with("}\n"));
+
+        assert!(json_set(content, "/missing/key", &json!(1)).is_err());
+        assert!(json_set(content, "/name/key", &json!(1)).is_err());
+        assert!(json_set("not json", "/a", &json!(1)).is_err());
+    }
+
+    #[test]
+    fn parse_xml_path_steps() {
+        let steps = parse_xml_path(
+            "/manifest/application/activity[@android:name='.MainActivity']/meta-data[2]",
+        )
+        .unwrap();
+        assert_eq!(steps.len(), 4);
+        assert_eq!(steps[0].name, "manifest");
+        assert_eq!(
+            steps[2].attr,
+            Some(("android:name".to_string(), ".MainActivity".to_string()))
+        );
+        assert_eq!(steps[3].name, "meta-data");
+        assert_eq!(steps[3].position, Some(2));
+        assert!(parse_xml_path("a/b[@name='x'").is_err());
+        assert!(parse_xml_path("a/b[0]").is_err());
+        assert!(parse_xml_path("a/b[@name]").is_err());
+    }
+
+    const MANIFEST: &str = r#"<manifest xmlns:android="http://schemas.android.com/apk/res/android">
+    <!-- <activity android:name=".Commented"/> -->
+    <application android:label="app">
+        <activity android:name=".Other" android:exported="false"/>
+        <activity android:name=".MainActivity" android:exported="true">
+            <meta-data android:name="a" android:value="1"/>
+        </activity>
+    </application>
+    <uses-sdk>21</uses-sdk>
+</manifest>
+"#;
+
+    #[test]
+    fn xml_set_attribute_in_place() {
+        let updated = xml_set(
+            MANIFEST,
+            "manifest/application/activity[@android:name='.MainActivity']",
+            Some("android:exported"),
+            "false",
+        )
+        .unwrap();
+        assert_eq!(
+            updated,
+            MANIFEST.replace(
+                "\".MainActivity\" android:exported=\"true\"",
+                "\".MainActivity\" android:exported=\"false\""
+            )
+        );
+        let updated = xml_set(
+            MANIFEST,
+            "manifest/application/activity[1]",
+            Some("android:label"),
+            "A & B",
+        )
+        .unwrap();
+        assert!(updated.contains("<activity android:name=\".Other\" android:exported=\"false\" android:label=\"A &amp; B\"/>"));
+        assert!(updated.contains("<!-- <activity android:name=\".Commented\"/> -->"));
+    }
+
+    #[test]
+    fn xml_set_text() {
+        let updated = xml_set(MANIFEST, "manifest/uses-sdk", None, "<23>").unwrap();
+        assert!(updated.contains("<uses-sdk>&lt;23&gt;</uses-sdk>"));
+        let updated = xml_set(
+            MANIFEST,
+            "manifest/application/activity[@android:name='.Other']",
+            None,
+            "x",
+        )
+        .unwrap();
+        assert!(updated.contains("android:exported=\"false\">x</activity>"));
+        assert!(xml_set(MANIFEST, "manifest/application", None, "x").is_err());
+        assert!(xml_set(MANIFEST, "manifest/missing", Some("a"), "x").is_err());
+        assert!(xml_set(MANIFEST, "root/application", Some("a"), "x").is_err());
+    }
+}
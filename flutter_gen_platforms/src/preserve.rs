@@ -0,0 +1,154 @@
+use anyhow::{Context, Result};
+use glob::{MatchOptions, Pattern};
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use crate::report::{self, FileAction};
+
+/// Stash location, relative to the project dir. `.dart_tool` is ignored by
+/// Flutter's default .gitignore, and a copy on disk survives a failed run.
+const STASH_DIR: &str = ".dart_tool/flutter_gen_platforms/preserve";
+
+/// `*` stays within one path component; `**` crosses directories.
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// Files of one platform directory that were moved aside before regeneration.
+pub struct Stash {
+    platform: String,
+    platform_dir: PathBuf,
+    stash_dir: PathBuf,
+    /// Paths relative to the platform dir.
+    files: Vec<PathBuf>,
+}
+
+/// Relative paths under `platform_dir` matching any of `patterns`.
+pub fn matching_files(platform_dir: &Path, patterns: &[String]) -> Result<Vec<PathBuf>> {
+    if patterns.is_empty() || !platform_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let patterns = patterns
+        .iter()
+        .map(|p| Pattern::new(p).with_context(|| format!("Invalid preserve pattern: {p}")))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut files = Vec::new();
+    for entry in WalkDir::new(platform_dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel = entry.path().strip_prefix(platform_dir)?;
+        if patterns
+            .iter()
+            .any(|p| p.matches_path_with(rel, MATCH_OPTIONS))
+        {
+            files.push(rel.to_path_buf());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Copy the files of `app_dir/<platform>` matching `patterns` into the
+/// project's stash directory. Returns `None` when nothing matches.
+pub fn stash_files(
+    project_dir: &Path,
+    app_dir: &Path,
+    platform: &str,
+    patterns: &[String],
+) -> Result<Option<Stash>> {
+    let platform_dir = app_dir.join(platform);
+    let stash_dir = project_dir.join(STASH_DIR).join(platform);
+    if stash_dir.exists() {
+        // A failed run may have removed the platform dir after stashing.
+        report::warn(format!(
+            "Restoring preserved {platform} files a failed run left in {}",
+            stash_dir.display()
+        ));
+        restore_leftover(&stash_dir, &platform_dir)?;
+    }
+
+    let files = matching_files(&platform_dir, patterns)?;
+    if files.is_empty() {
+        return Ok(None);
+    }
+    for rel in &files {
+        copy_file(&platform_dir.join(rel), &stash_dir.join(rel))?;
+    }
+    log!("✓ Stashed {} preserved {platform} file(s)", files.len());
+
+    Ok(Some(Stash {
+        platform: platform.to_string(),
+        platform_dir,
+        stash_dir,
+        files,
+    }))
+}
+
+impl Stash {
+    /// Copy the stashed files back over the regenerated platform dir and
+    /// remove the stash.
+    pub fn restore(self) -> Result<()> {
+        for rel in &self.files {
+            let dst = self.platform_dir.join(rel);
+            copy_file(&self.stash_dir.join(rel), &dst)?;
+            report::file(FileAction::Copied, &dst);
+        }
+        fs::remove_dir_all(&self.stash_dir)
+            .with_context(|| format!("Failed to remove dir: {}", self.stash_dir.display()))?;
+        // Drop the now-empty stash root; other platforms may still be using it.
+        for dir in self.stash_dir.ancestors().skip(1).take(2) {
+            let _ = fs::remove_dir(dir);
+        }
+        log!(
+            "✓ Restored {} preserved {} file(s)",
+            self.files.len(),
+            self.platform
+        );
+        Ok(())
+    }
+}
+
+/// Copy everything in a leftover stash back into `platform_dir` and remove it.
+fn restore_leftover(stash_dir: &Path, platform_dir: &Path) -> Result<()> {
+    for entry in WalkDir::new(stash_dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            let rel = entry.path().strip_prefix(stash_dir)?;
+            copy_file(entry.path(), &platform_dir.join(rel))?;
+        }
+    }
+    fs::remove_dir_all(stash_dir)
+        .with_context(|| format!("Failed to remove dir: {}", stash_dir.display()))
+}
+
+/// Warn about preserved files that another step also writes. `writers` pairs
+/// a path with the step that writes it.
+pub fn report_conflicts(stash: &Stash, writers: &[(PathBuf, String)]) {
+    for rel in &stash.files {
+        let path = stash.platform_dir.join(rel);
+        for (written, source) in writers {
+            if *written == path {
+                report::warn(format!(
+                    "Preserved file {} conflicts with {source}",
+                    path.display()
+                ));
+            }
+        }
+    }
+}
+
+fn copy_file(src: &Path, dst: &Path) -> Result<()> {
+    if let Some(parent) = dst.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+    }
+    fs::copy(src, dst)
+        .with_context(|| format!("Failed to copy {} -> {}", src.display(), dst.display()))?;
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+
+use crate::config::ProguardConfig;
+use crate::gradle::{find_block, find_build_type, set_call, set_property, APP_GRADLE_PATH};
+use crate::report::{self, FileAction};
+
+/// Keep-rule presets for plugins and libraries that commonly break under R8.
+const PRESETS: &[(&str, &str)] = &[
+    (
+        "play_core",
+        "# Flutter deferred components reference Play Core classes that most apps don't ship\n\
+         -dontwarn com.google.android.play.core.**",
+    ),
+    (
+        "gson",
+        "-keepattributes Signature\n\
+         -keepattributes *Annotation*\n\
+         -dontwarn sun.misc.**\n\
+         -keep class com.google.gson.reflect.TypeToken { *; }\n\
+         -keep class * extends com.google.gson.reflect.TypeToken\n\
+         -keep class * implements com.google.gson.TypeAdapterFactory\n\
+         -keep class * implements com.google.gson.JsonSerializer\n\
+         -keep class * implements com.google.gson.JsonDeserializer",
+    ),
+    (
+        "okhttp",
+        "-dontwarn okhttp3.internal.platform.**\n\
+         -dontwarn org.conscrypt.**\n\
+         -dontwarn org.bouncycastle.**\n\
+         -dontwarn org.openjsse.**",
+    ),
+    (
+        "flutter_local_notifications",
+        "-keep class com.dexterous.flutterlocalnotifications.** { *; }\n\
+         -keepattributes Signature\n\
+         -keep class com.google.gson.reflect.TypeToken { *; }\n\
+         -keep class * extends com.google.gson.reflect.TypeToken",
+    ),
+    (
+        "kotlinx_serialization",
+        "-keepattributes *Annotation*, InnerClasses\n\
+         -dontnote kotlinx.serialization.**\n\
+         -keepclassmembers class kotlinx.serialization.json.** { *** Companion; }\n\
+         -keep,includedescriptorclasses class **$$serializer { *; }",
+    ),
+];
+
+/// Rules file name, relative to android/app/.
+const RULES_FILE: &str = "proguard-rules.pro";
+
+/// Assemble `proguard-rules.pro` from presets and custom rules, and set
+/// `isMinifyEnabled`/`isShrinkResources`/`proguardFiles` per build type.
+pub fn apply_proguard_config(android_dir: &Path, config: &ProguardConfig) -> Result<()> {
+    let rules = assemble_rules(config)?;
+    let rules_path = android_dir.join("app").join(RULES_FILE);
+    // Keep rules shipped by the overlay and append the generated ones.
+    let existing_rules = rules_path.exists();
+    let existing = if existing_rules {
+        fs::read_to_string(&rules_path)
+            .with_context(|| format!("Failed to read: {}", rules_path.display()))?
+    } else {
+        String::new()
+    };
+    if !rules.is_empty() {
+        let mut content = existing;
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&rules);
+        fs::write(&rules_path, content)
+            .with_context(|| format!("Failed to write: {}", rules_path.display()))?;
+        let action = if existing_rules {
+            FileAction::Patched
+        } else {
+            FileAction::Created
+        };
+        report::file(action, &rules_path);
+    }
+
+    if !config.build_types.is_empty() {
+        let gradle_path = android_dir.join(APP_GRADLE_PATH);
+        let mut content = fs::read_to_string(&gradle_path)
+            .with_context(|| format!("Failed to read: {}", gradle_path.display()))?;
+        for (name, build_type) in &config.build_types {
+            if build_type.shrink_resources && !build_type.minify {
+                bail!(
+                    "android.proguard.build_types.{name}: shrink_resources requires minify = true"
+                );
+            }
+            content = apply_build_type(
+                &content,
+                name,
+                build_type.minify,
+                build_type.shrink_resources,
+            )
+            .with_context(|| format!("Failed to update {}", gradle_path.display()))?;
+        }
+        fs::write(&gradle_path, content)
+            .with_context(|| format!("Failed to write: {}", gradle_path.display()))?;
+        report::file(FileAction::Patched, &gradle_path);
+    }
+
+    log!("✓ Android R8/ProGuard configuration applied");
+    Ok(())
+}
+
+fn assemble_rules(config: &ProguardConfig) -> Result<String> {
+    let mut out = String::new();
+    if config.presets.is_empty() && config.rules.is_empty() {
+        return Ok(out);
+    }
+    out.push_str("# --- Generated by flutter_gen_platforms ---\n");
+    for preset in &config.presets {
+        let Some((_, rules)) = PRESETS.iter().find(|(name, _)| name == preset) else {
+            bail!(
+                "Unknown proguard preset '{}' (available: {})",
+                preset,
+                PRESETS
+                    .iter()
+                    .map(|(name, _)| *name)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            );
+        };
+        out.push_str(&format!("\n# preset: {preset}\n{rules}\n"));
+    }
+    if !config.rules.is_empty() {
+        out.push_str("\n# custom rules\n");
+        for rule in &config.rules {
+            out.push_str(rule.trim_end());
+            out.push('\n');
+        }
+    }
+    Ok(out)
+}
+
+fn apply_build_type(content: &str, name: &str, minify: bool, shrink: bool) -> Result<String> {
+    let android =
+        find_block(content, "android", 0..content.len()).context("No android { } block found")?;
+    let Some(build_types) = find_block(content, "buildTypes", android.clone()) else {
+        bail!("No buildTypes {{ }} block found inside android {{ }}");
+    };
+
+    // Create the build type block when the script doesn't declare it yet.
+    let content = if find_build_type(content, build_types.clone(), name).is_none() {
+        let declaration = if name == "release" || name == "debug" {
+            format!("getByName(\"{name}\")")
+        } else {
+            format!("create(\"{name}\")")
+        };
+        let insert_at = build_types.start + content[build_types.clone()].trim_end().len();
+        let indent = "        ";
+        format!(
+            "{}\n{indent}{declaration} {{\n{indent}}}{}",
+            &content[..insert_at],
+            &content[insert_at..]
+        )
+    } else {
+        content.to_string()
+    };
+
+    let mut content = content;
+    for (key, value) in [("isMinifyEnabled", minify), ("isShrinkResources", shrink)] {
+        let body = locate_build_type(&content, name)?;
+        content = set_property(&content, body, key, &value.to_string());
+    }
+    if minify {
+        let body = locate_build_type(&content, name)?;
+        content = set_call(
+            &content,
+            body,
+            "proguardFiles",
+            &format!("getDefaultProguardFile(\"proguard-android-optimize.txt\"), \"{RULES_FILE}\""),
+        );
+    }
+    Ok(content)
+}
+
+fn locate_build_type(content: &str, name: &str) -> Result<std::ops::Range<usize>> {
+    let android =
+        find_block(content, "android", 0..content.len()).context("No android { } block found")?;
+    let build_types = find_block(content, "buildTypes", android)
+        .context("No buildTypes { } block found inside android { }")?;
+    find_build_type(content, build_types, name)
+        .with_context(|| format!("Build type '{name}' not found"))
+}
@@ -0,0 +1,249 @@
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use walkdir::WalkDir;
+
+/// Template var names containing one of these are redacted in the report.
+const SECRET_MARKERS: &[&str] = &["password", "secret", "token", "credential", "api_key"];
+
+#[derive(Copy, Clone, Debug, ValueEnum)]
+pub enum ReportFormat {
+    Json,
+}
+
+/// What a generation step did to a file.
+#[derive(Copy, Clone, Debug)]
+pub enum FileAction {
+    /// Written from scratch (by flutter create or by this tool).
+    Created,
+    /// Copied from the overlay with `{{var}}` substitution.
+    Templated,
+    /// Copied verbatim (overlay, Firebase, brand assets, preserved files).
+    Copied,
+    /// Edited in place.
+    Patched,
+}
+
+#[derive(Debug, Default, Serialize)]
+struct Files {
+    created: BTreeSet<String>,
+    templated: BTreeSet<String>,
+    copied: BTreeSet<String>,
+    patched: BTreeSet<String>,
+    /// Path → reason.
+    skipped: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Serialize)]
+struct FlutterRun {
+    command: Vec<String>,
+    duration_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct FlutterSdk {
+    version: String,
+    channel: String,
+}
+
+/// Summary of one `generate` run (one per brand with `--all-brands`).
+#[derive(Debug, Serialize)]
+struct Generation {
+    project_dir: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    brand: Option<String>,
+    dry_run: bool,
+    platforms: Vec<String>,
+    template_vars: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flutter_sdk: Option<FlutterSdk>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flutter: Option<FlutterRun>,
+    files: Files,
+    warnings: Vec<String>,
+    #[serde(skip)]
+    root: PathBuf,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    generations: &'a [Generation],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+static GENERATIONS: Mutex<Vec<Generation>> = Mutex::new(Vec::new());
+
+static PROGRESS_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Send progress output (and that of child processes) to stderr, leaving
+/// stdout to the report.
+pub fn progress_to_stderr() {
+    PROGRESS_TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+pub fn progress_on_stderr() -> bool {
+    PROGRESS_TO_STDERR.load(Ordering::Relaxed)
+}
+
+/// Stdout for child processes such as `flutter create` and hooks.
+pub fn child_stdout() -> Stdio {
+    if progress_on_stderr() {
+        std::io::stderr().into()
+    } else {
+        Stdio::inherit()
+    }
+}
+
+fn with_current(f: impl FnOnce(&mut Generation)) {
+    let mut generations = GENERATIONS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(generation) = generations.last_mut() {
+        f(generation);
+    }
+}
+
+/// Path relative to the generation's project dir, with `/` separators so
+/// reports diff cleanly across hosts.
+fn relative(root: &Path, path: &Path) -> String {
+    path.strip_prefix(root)
+        .unwrap_or(path)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
+
+/// Start recording a generation of `project_dir`.
+pub fn begin(project_dir: &Path, brand: Option<&str>, dry_run: bool) {
+    let mut generations = GENERATIONS.lock().unwrap_or_else(|e| e.into_inner());
+    generations.push(Generation {
+        project_dir: project_dir.display().to_string(),
+        brand: brand.map(str::to_string),
+        dry_run,
+        platforms: Vec::new(),
+        template_vars: BTreeMap::new(),
+        flutter_sdk: None,
+        flutter: None,
+        files: Files::default(),
+        warnings: Vec::new(),
+        root: project_dir.to_path_buf(),
+    });
+}
+
+/// Record the processed platforms and resolved template vars.
+pub fn inputs(platforms: &[&str], template_vars: &HashMap<String, String>) {
+    with_current(|generation| {
+        generation.platforms = platforms.iter().map(|p| p.to_string()).collect();
+        generation.template_vars = template_vars
+            .iter()
+            .map(|(key, value)| {
+                let lower = key.to_lowercase();
+                let value = if SECRET_MARKERS.iter().any(|m| lower.contains(m)) {
+                    "<redacted>".to_string()
+                } else {
+                    value.clone()
+                };
+                (key.clone(), value)
+            })
+            .collect();
+    });
+}
+
+pub fn file(action: FileAction, path: &Path) {
+    with_current(|generation| {
+        let path = relative(&generation.root, path);
+        let files = &mut generation.files;
+        match action {
+            FileAction::Created => files.created.insert(path),
+            FileAction::Templated => files.templated.insert(path),
+            FileAction::Copied => files.copied.insert(path),
+            FileAction::Patched => files.patched.insert(path),
+        };
+    });
+}
+
+/// Record every file under `dir`.
+pub fn files_under(action: FileAction, dir: &Path) -> Result<()> {
+    if !dir.exists() {
+        return Ok(());
+    }
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            file(action, entry.path());
+        }
+    }
+    Ok(())
+}
+
+pub fn skipped(path: &Path, reason: &str) {
+    with_current(|generation| {
+        let path = relative(&generation.root, path);
+        generation.files.skipped.insert(path, reason.to_string());
+    });
+}
+
+/// Print a `⚠` warning and record it.
+pub fn warn(message: impl Into<String>) {
+    let message = message.into();
+    log!("⚠ {message}");
+    with_current(|generation| generation.warnings.push(message));
+}
+
+pub fn flutter_sdk(version: &str, channel: &str) {
+    with_current(|generation| {
+        generation.flutter_sdk = Some(FlutterSdk {
+            version: version.to_string(),
+            channel: channel.to_string(),
+        });
+    });
+}
+
+pub fn flutter_command(command: &Command, duration: Duration) {
+    let mut args = vec![command.get_program().to_string_lossy().into_owned()];
+    args.extend(
+        command
+            .get_args()
+            .map(|arg| arg.to_string_lossy().into_owned()),
+    );
+    with_current(|generation| {
+        generation.flutter = Some(FlutterRun {
+            command: args,
+            duration_ms: duration.as_millis() as u64,
+        });
+    });
+}
+
+/// Write the recorded generations to `output` (stdout when `None`), along
+/// with the error that ended the run, if any.
+pub fn write(
+    format: ReportFormat,
+    output: Option<&Path>,
+    error: Option<&anyhow::Error>,
+) -> Result<()> {
+    let generations = GENERATIONS.lock().unwrap_or_else(|e| e.into_inner());
+    let report = Report {
+        generations: &generations,
+        error: error.map(|e| format!("{e:#}")),
+    };
+    let content = match format {
+        ReportFormat::Json => serde_json::to_string_pretty(&report)?,
+    };
+    match output {
+        Some(path) => {
+            if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+                fs::create_dir_all(parent)
+                    .with_context(|| format!("Failed to create dir: {}", parent.display()))?;
+            }
+            fs::write(path, format!("{content}\n"))
+                .with_context(|| format!("Failed to write report: {}", path.display()))?;
+        }
+        None => println!("{content}"),
+    }
+    Ok(())
+}
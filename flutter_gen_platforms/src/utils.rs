@@ -2,9 +2,11 @@ use anyhow::{bail, Context, Result};
 use std::fs;
 use std::path::Path;
 use std::process::Command;
+use std::time::Instant;
 use which::which;
 
 use crate::config::FlutterCreateConfig;
+use crate::report;
 
 pub fn resolve_cmd(command: &str) -> Result<std::path::PathBuf> {
     if command.contains(['/', '\\']) {
@@ -17,6 +19,14 @@ pub fn resolve_cmd(command: &str) -> Result<std::path::PathBuf> {
     which(command).with_context(|| format!("command not found in PATH: {command}"))
 }
 
+/// Directory holding the per-platform overlays (default: `platforms`).
+pub fn platforms_root(platforms_dir: Option<&str>) -> &str {
+    platforms_dir
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+        .unwrap_or("platforms")
+}
+
 pub fn run_flutter_create(
     path: &Path,
     flutter_cmd: &Path,
@@ -30,24 +40,54 @@ pub fn run_flutter_create(
         .arg("create")
         .arg("--project-name")
         .arg(project_name);
-    if let Some(platforms) = create.platforms.as_ref() {
-        if !platforms.is_empty() {
-            command.arg("--platforms").arg(platforms.join(","));
-        }
+    if let Some(value) = create.template.as_deref() {
+        command.arg("--template").arg(value);
+    }
+    // --platforms is only accepted by the app and plugin templates.
+    let platforms = if create.is_plugin() {
+        create
+            .plugin_platforms
+            .as_ref()
+            .or(create.platforms.as_ref())
+    } else if create.template() == "app" {
+        create.platforms.as_ref()
+    } else {
+        None
+    };
+    if let Some(platforms) = platforms.filter(|platforms| !platforms.is_empty()) {
+        command.arg("--platforms").arg(platforms.join(","));
     }
     if let Some(value) = create.android_language.as_deref() {
         command.arg("--android-language").arg(value);
     }
+    if let Some(value) = create.ios_language.as_deref() {
+        command.arg("--ios-language").arg(value);
+    }
+    if let Some(value) = create.sample.as_deref() {
+        command.arg("--sample").arg(value);
+    }
+    if create.empty {
+        command.arg("--empty");
+    }
+    if create.no_pub {
+        command.arg("--no-pub");
+    }
+    if create.offline {
+        command.arg("--offline");
+    }
     if let Some(value) = org {
         command.arg("--org").arg(value);
     }
     if let Some(value) = description {
         command.arg("--description").arg(value);
     }
+    command.arg(path);
+    let started = Instant::now();
     let status = command
-        .arg(path)
+        .stdout(report::child_stdout())
         .status()
         .context("Failed to run flutter create")?;
+    report::flutter_command(&command, started.elapsed());
     if !status.success() {
         bail!("flutter create failed with status: {status}");
     }
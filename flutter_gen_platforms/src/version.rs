@@ -0,0 +1,335 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::ser::{PrettyFormatter, Serializer};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::gradle::{find_block, set_property, APP_GRADLE_PATH};
+use crate::report::{self, FileAction};
+
+/// Largest `versionCode` Google Play accepts.
+const MAX_VERSION_CODE: u64 = 2_100_000_000;
+
+/// A pubspec-style version (`1.2.3+45`) split into name and build number.
+#[derive(Debug, Clone)]
+pub struct AppVersion {
+    /// `1.2.3` or `1.2.3-beta.1`.
+    pub name: String,
+    pub build: Option<u64>,
+    /// Major, minor and patch of `name`.
+    pub parts: [u64; 3],
+}
+
+impl AppVersion {
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let (name, build) = match value.split_once('+') {
+            Some((name, build)) => {
+                let build = build.parse::<u64>().with_context(|| {
+                    format!(
+                        "Invalid build number in version '{value}': expected an integer after '+'"
+                    )
+                })?;
+                if build > MAX_VERSION_CODE {
+                    bail!("Build number {build} in version '{value}' exceeds {MAX_VERSION_CODE}");
+                }
+                (name, Some(build))
+            }
+            None => (value, None),
+        };
+
+        let core = name.split('-').next().unwrap_or_default();
+        let parts = core
+            .split('.')
+            .map(|part| part.parse::<u64>().ok())
+            .collect::<Option<Vec<_>>>()
+            .filter(|parts| parts.len() == 3)
+            .with_context(|| format!("Invalid version '{value}': expected MAJOR.MINOR.PATCH"))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            build,
+            parts: [parts[0], parts[1], parts[2]],
+        })
+    }
+}
+
+impl std::fmt::Display for AppVersion {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.build {
+            Some(build) => write!(f, "{}+{}", self.name, build),
+            None => f.write_str(&self.name),
+        }
+    }
+}
+
+/// Set `versionName`/`versionCode` in `defaultConfig` of app/build.gradle.kts,
+/// overriding the values Flutter would take from pubspec.yaml.
+pub fn apply_android_version(android_dir: &Path, version: &AppVersion) -> Result<()> {
+    let path = android_dir.join(APP_GRADLE_PATH);
+    let mut content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read: {}", path.display()))?;
+
+    let mut entries = vec![("versionName", format!("\"{}\"", version.name))];
+    if let Some(build) = version.build {
+        entries.push(("versionCode", build.to_string()));
+    }
+    for (key, value) in entries {
+        let android = find_block(&content, "android", 0..content.len())
+            .with_context(|| format!("No android {{ }} block found in: {}", path.display()))?;
+        let default_config = find_block(&content, "defaultConfig", android).with_context(|| {
+            format!("No defaultConfig {{ }} block found in: {}", path.display())
+        })?;
+        content = set_property(&content, default_config, key, &value);
+    }
+    fs::write(&path, content).with_context(|| format!("Failed to write: {}", path.display()))?;
+    report::file(FileAction::Patched, &path);
+
+    log!("✓ Android version set to {version}");
+    Ok(())
+}
+
+/// Rewrite the `VERSION_AS_NUMBER`/`VERSION_AS_STRING` defines in
+/// windows/runner/Runner.rc.
+pub fn apply_windows_version(windows_dir: &Path, version: &AppVersion) -> Result<()> {
+    let path = windows_dir.join("runner").join("Runner.rc");
+    if !path.exists() {
+        report::warn(format!(
+            "Skipping Windows version: {} not found",
+            path.display()
+        ));
+        report::skipped(&path, "not generated by flutter create");
+        return Ok(());
+    }
+    let build = version.build.unwrap_or(0);
+    // VERSIONINFO fields are 16-bit.
+    if version
+        .parts
+        .iter()
+        .chain([&build])
+        .any(|part| *part > 0xFFFF)
+    {
+        bail!("Version {version} does not fit Windows VERSIONINFO (each part must be <= 65535)");
+    }
+
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read: {}", path.display()))?;
+    let [major, minor, patch] = version.parts;
+    let number = format!("#define VERSION_AS_NUMBER {major},{minor},{patch},{build}");
+    let string = format!("#define VERSION_AS_STRING \"{}\"", version.name);
+
+    let mut found = (false, false);
+    let updated = content
+        .split_inclusive('\n')
+        .map(|line| {
+            let body = line.trim_end_matches(['\r', '\n']);
+            let ending = &line[body.len()..];
+            if body.trim_start().starts_with("#define VERSION_AS_NUMBER") {
+                found.0 = true;
+                format!("{number}{ending}")
+            } else if body.trim_start().starts_with("#define VERSION_AS_STRING") {
+                found.1 = true;
+                format!("{string}{ending}")
+            } else {
+                line.to_string()
+            }
+        })
+        .collect::<String>();
+    if found != (true, true) {
+        bail!(
+            "VERSION_AS_NUMBER/VERSION_AS_STRING defines not found in: {}",
+            path.display()
+        );
+    }
+    fs::write(&path, updated).with_context(|| format!("Failed to write: {}", path.display()))?;
+    report::file(FileAction::Patched, &path);
+
+    log!("✓ Windows Runner.rc version set to {version}");
+    Ok(())
+}
+
+/// Set `version` in web/manifest.json.
+pub fn apply_web_version(web_dir: &Path, version: &AppVersion) -> Result<()> {
+    let path = web_dir.join("manifest.json");
+    if !path.exists() {
+        report::warn(format!(
+            "Skipping web version: {} not found",
+            path.display()
+        ));
+        report::skipped(&path, "not generated by flutter create");
+        return Ok(());
+    }
+    update_web_manifest(&path, |manifest| {
+        manifest.insert("version".to_string(), Value::String(version.name.clone()));
+    })?;
+
+    log!("✓ Web manifest version set to {}", version.name);
+    Ok(())
+}
+
+/// Edit the top-level object of web/manifest.json in place.
+pub fn update_web_manifest(path: &Path, edit: impl FnOnce(&mut Map<String, Value>)) -> Result<()> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Failed to read: {}", path.display()))?;
+    let updated = edit_web_manifest(&content, edit)
+        .with_context(|| format!("Failed to update: {}", path.display()))?;
+    fs::write(path, updated).with_context(|| format!("Failed to write: {}", path.display()))?;
+    report::file(FileAction::Patched, path);
+    Ok(())
+}
+
+/// Apply `edit` to the top-level object of a web manifest, writing it back
+/// with the 4-space indent `flutter create` uses and the original trailing
+/// newline, so an unchanged manifest stays byte-identical.
+fn edit_web_manifest(content: &str, edit: impl FnOnce(&mut Map<String, Value>)) -> Result<String> {
+    let mut manifest: Value = serde_json::from_str(content).context("Failed to parse JSON")?;
+    let Some(object) = manifest.as_object_mut() else {
+        bail!("Expected a JSON object");
+    };
+    edit(object);
+    let mut out = Vec::with_capacity(content.len());
+    let formatter = PrettyFormatter::with_indent(b"    ");
+    manifest.serialize(&mut Serializer::with_formatter(&mut out, formatter))?;
+    let mut updated = String::from_utf8(out)?;
+    if content.ends_with('\n') {
+        updated.push('\n');
+    }
+    Ok(updated)
+}
+
+/// Set `CFBundleShortVersionString` (and `CFBundleVersion` with a build
+/// number) in ios/Runner/Info.plist when the iOS project exists.
+pub fn apply_ios_version(app_dir: &Path, version: &AppVersion) -> Result<()> {
+    let path = app_dir.join("ios").join("Runner").join("Info.plist");
+    if !path.exists() {
+        return Ok(());
+    }
+    let mut content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read: {}", path.display()))?;
+
+    content = set_plist_string(&content, "CFBundleShortVersionString", &version.name)
+        .with_context(|| format!("Failed to update {}", path.display()))?;
+    if let Some(build) = version.build {
+        content = set_plist_string(&content, "CFBundleVersion", &build.to_string())
+            .with_context(|| format!("Failed to update {}", path.display()))?;
+    }
+    fs::write(&path, content).with_context(|| format!("Failed to write: {}", path.display()))?;
+    report::file(FileAction::Patched, &path);
+
+    log!("✓ iOS Info.plist version set to {version}");
+    Ok(())
+}
+
+/// Replace the `<string>` value following `<key>{key}</key>`.
+pub fn set_plist_string(content: &str, key: &str, value: &str) -> Result<String> {
+    let key_tag = format!("<key>{key}</key>");
+    let key_end = content
+        .find(&key_tag)
+        .map(|pos| pos + key_tag.len())
+        .with_context(|| format!("{key} not found"))?;
+    let rest = &content[key_end..];
+    let open = rest
+        .find("<string>")
+        .filter(|pos| rest[..*pos].trim().is_empty())
+        .with_context(|| format!("{key} is not followed by a <string> value"))?;
+    let value_start = key_end + open + "<string>".len();
+    let value_end = content[value_start..]
+        .find("</string>")
+        .map(|pos| value_start + pos)
+        .with_context(|| format!("Unclosed <string> for {key}"))?;
+    Ok(format!(
+        "{}{}{}",
+        &content[..value_start],
+        value.replace('&', "&amp;").replace('<', "&lt;"),
+        &content[value_end..]
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FLUTTER_MANIFEST: &str = r##"{
+    "name": "myapp",
+    "short_name": "myapp",
+    "start_url": ".",
+    "display": "standalone",
+    "background_color": "#0175C2",
+    "prefer_related_applications": false,
+    "icons": [
+        {
+            "src": "icons/Icon-192.png",
+            "sizes": "192x192",
+            "type": "image/png"
+        }
+    ]
+}"##;
+
+    #[test]
+    fn edit_web_manifest_keeps_flutter_format() {
+        assert_eq!(
+            edit_web_manifest(FLUTTER_MANIFEST, |_| {}).unwrap(),
+            FLUTTER_MANIFEST
+        );
+        let with_newline = format!("{FLUTTER_MANIFEST}\n");
+        assert_eq!(
+            edit_web_manifest(&with_newline, |_| {}).unwrap(),
+            with_newline
+        );
+    }
+
+    #[test]
+    fn edit_web_manifest_changes_only_the_value() {
+        let updated = edit_web_manifest(FLUTTER_MANIFEST, |manifest| {
+            manifest.insert("short_name".to_string(), Value::from("Shop"));
+            manifest.insert("version".to_string(), Value::from("1.2.3"));
+        })
+        .unwrap();
+        let expected = FLUTTER_MANIFEST
+            .replace("\"short_name\": \"myapp\"", "\"short_name\": \"Shop\"")
+            .replace("\n    ]\n}", "\n    ],\n    \"version\": \"1.2.3\"\n}");
+        assert_eq!(updated, expected);
+        assert!(edit_web_manifest("[]", |_| {}).is_err());
+    }
+
+    #[test]
+    fn app_version_parse() {
+        let version = AppVersion::parse(" 1.2.3+45 ").unwrap();
+        assert_eq!(version.name, "1.2.3");
+        assert_eq!(version.build, Some(45));
+        assert_eq!(version.parts, [1, 2, 3]);
+        assert_eq!(version.to_string(), "1.2.3+45");
+
+        let version = AppVersion::parse("2.0.0-beta.1").unwrap();
+        assert_eq!(version.name, "2.0.0-beta.1");
+        assert_eq!(version.build, None);
+        assert_eq!(version.parts, [2, 0, 0]);
+    }
+
+    #[test]
+    fn app_version_parse_errors() {
+        for invalid in ["1.2", "1.2.3.4", "v1.2.3", "1.2.x", "1.2.3+build", "1.2.3+"] {
+            assert!(AppVersion::parse(invalid).is_err(), "{invalid}");
+        }
+        let too_big = format!("1.0.0+{}", MAX_VERSION_CODE + 1);
+        assert!(AppVersion::parse(&too_big).is_err());
+        assert!(AppVersion::parse(&format!("1.0.0+{MAX_VERSION_CODE}")).is_ok());
+    }
+
+    #[test]
+    fn set_plist_string_value() {
+        let plist = "<dict>\n\t<key>CFBundleVersion</key>\n\t<string>$(FLUTTER_BUILD_NUMBER)</string>\n\t<key>Other</key>\n\t<string>x</string>\n</dict>\n";
+        assert_eq!(
+            set_plist_string(plist, "CFBundleVersion", "42").unwrap(),
+            plist.replace("$(FLUTTER_BUILD_NUMBER)", "42")
+        );
+        assert_eq!(
+            set_plist_string(plist, "Other", "a & <b>").unwrap(),
+            plist.replace("<string>x</string>", "<string>a &amp; &lt;b></string>")
+        );
+        assert!(set_plist_string(plist, "Missing", "1").is_err());
+        let not_string = "<key>CFBundleVersion</key>\n<integer>1</integer>\n<string>x</string>";
+        assert!(set_plist_string(not_string, "CFBundleVersion", "2").is_err());
+    }
+}
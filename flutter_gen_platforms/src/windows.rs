@@ -3,6 +3,7 @@ use std::fs;
 use std::path::Path;
 
 use crate::config::WindowsConfig;
+use crate::report::{self, FileAction};
 
 /// Process Windows platform directory
 pub fn process_windows_platform(project_dir: &Path, config: &WindowsConfig) -> Result<()> {
@@ -37,15 +38,17 @@ pub fn process_windows_platform(project_dir: &Path, config: &WindowsConfig) -> R
                 .join("\n");
 
             fs::write(&main_cpp_path, updated_content).context("Failed to write main.cpp")?;
+            report::file(FileAction::Patched, &main_cpp_path);
 
-            println!(
+            log!(
                 "✓ Windows main.cpp updated with window size {}x{}",
-                width, height
+                width,
+                height
             );
         }
     }
 
-    println!("✓ Windows platform directory configured");
+    log!("✓ Windows platform directory configured");
 
     Ok(())
 }